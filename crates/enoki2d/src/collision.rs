@@ -0,0 +1,645 @@
+use crate::update::ParticleStore;
+use bevy_app::{App, Plugin};
+use bevy_ecs::{
+    component::Component,
+    reflect::{ReflectComponent, ReflectResource},
+    resource::Resource,
+    system::{Query, ResMut},
+    world::{FilteredResources, FilteredResourcesBuilder},
+};
+use bevy_math::{EulerRot, IVec2, Rot2, UVec2, Vec2};
+use bevy_reflect::{prelude::ReflectDefault, Reflect};
+use bevy_transform::components::{GlobalTransform, Transform};
+use serde::{Deserialize, Serialize};
+use std::{any::TypeId, marker::PhantomData};
+
+/// What happens to particles, that hit a `ParticleCollider2d`.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum CollisionResponse {
+    /// `restitution` keeps the speed away from the surface,
+    /// `friction` removes the speed along it. Both between 0 - 1.
+    Bounce { restitution: f32, friction: f32 },
+    /// stops on the surface
+    Stick,
+    /// removes the particle
+    Die,
+}
+
+/// A solid shape particles collide with, if their effect has a `collision` response.
+/// The shape moves and rotates with the entity.
+#[derive(Component, Clone, Debug, Reflect)]
+#[reflect(Component, Debug)]
+#[require(Transform)]
+pub struct ParticleCollider2d(pub ColliderShape);
+
+#[derive(Clone, Debug, PartialEq, Reflect)]
+pub enum ColliderShape {
+    /// everything behind the line through the origin, facing `normal`
+    HalfPlane {
+        normal: Vec2,
+    },
+    Circle(f32),
+    Rectangle {
+        half_size: Vec2,
+    },
+    /// connected line segments, solid from both sides
+    Polyline(Vec<Vec2>),
+}
+
+/// Solid geometry, that is too large for `ParticleCollider2d` entities, like tile maps.
+/// Implement it on a resource and add the `ParticleCollisionQueryPlugin` for it.
+pub trait ParticleCollisionQuery: Send + Sync + 'static {
+    /// true, when the world position is inside solid geometry
+    fn is_solid(&self, position: Vec2) -> bool;
+    /// surface normal for a particle, that moved from `previous` into the solid `position`
+    fn normal(&self, previous: Vec2, position: Vec2) -> Vec2;
+}
+
+/// Lets particles collide with the resource `T`. The resource is read in place
+/// by the particle update, so it has to be added before the app starts.
+/// `ParticleCollisionGrid` is always registered by the `EnokiPlugin`.
+pub struct ParticleCollisionQueryPlugin<T: ParticleCollisionQuery + Resource> {
+    _t: PhantomData<T>,
+}
+
+impl<T: ParticleCollisionQuery + Resource> Default for ParticleCollisionQueryPlugin<T> {
+    fn default() -> Self {
+        Self {
+            _t: PhantomData::<T>,
+        }
+    }
+}
+
+impl<T: ParticleCollisionQuery + Resource> Plugin for ParticleCollisionQueryPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ParticleCollisionQueries>();
+        app.world_mut()
+            .resource_mut::<ParticleCollisionQueries>()
+            .register::<T>();
+    }
+}
+
+/// every resource type particles collide with
+#[derive(Resource, Default, Clone)]
+pub(crate) struct ParticleCollisionQueries(Vec<QueryEntry>);
+
+#[derive(Clone, Copy)]
+struct QueryEntry {
+    id: TypeId,
+    add_read: fn(&mut FilteredResourcesBuilder),
+    get: for<'w, 's> fn(&FilteredResources<'w, 's>) -> Option<&'w dyn ParticleCollisionQuery>,
+}
+
+impl ParticleCollisionQueries {
+    pub(crate) fn register<T: ParticleCollisionQuery + Resource>(&mut self) {
+        if self.0.iter().any(|entry| entry.id == TypeId::of::<T>()) {
+            return;
+        }
+        self.0.push(QueryEntry {
+            id: TypeId::of::<T>(),
+            add_read: |builder| {
+                builder.add_read::<T>();
+            },
+            get: get_query::<T>,
+        });
+    }
+
+    /// read access to every registered resource
+    pub(crate) fn add_reads(&self, builder: &mut FilteredResourcesBuilder) {
+        for entry in &self.0 {
+            (entry.add_read)(builder);
+        }
+    }
+
+    /// the registered resources, that currently exist
+    pub(crate) fn collect<'w>(
+        &self,
+        resources: &FilteredResources<'w, '_>,
+    ) -> Vec<&'w dyn ParticleCollisionQuery> {
+        self.0
+            .iter()
+            .filter_map(|entry| (entry.get)(resources))
+            .collect()
+    }
+}
+
+fn get_query<'w, T: ParticleCollisionQuery + Resource>(
+    resources: &FilteredResources<'w, '_>,
+) -> Option<&'w dyn ParticleCollisionQuery> {
+    resources
+        .get::<T>()
+        .ok()
+        .map(|query| query.into_inner() as &dyn ParticleCollisionQuery)
+}
+
+/// A tile grid of solid and empty cells, bit packed.
+/// Cells outside of the grid are empty.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource, Debug, Default)]
+pub struct ParticleCollisionGrid {
+    /// world position of the lower left corner of cell `(0, 0)`
+    pub origin: Vec2,
+    pub cell_size: Vec2,
+    size: UVec2,
+    cells: Vec<u32>,
+}
+
+impl ParticleCollisionGrid {
+    /// an empty grid of `size` cells
+    pub fn new(size: UVec2, cell_size: Vec2, origin: Vec2) -> Self {
+        Self {
+            origin,
+            cell_size,
+            size,
+            cells: vec![0; (size.element_product() as usize).div_ceil(32)],
+        }
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    pub fn set(&mut self, cell: UVec2, solid: bool) {
+        let Some(index) = self.index(cell.as_ivec2()) else {
+            return;
+        };
+        if solid {
+            self.cells[index / 32] |= 1 << (index % 32);
+        } else {
+            self.cells[index / 32] &= !(1 << (index % 32));
+        }
+    }
+
+    pub fn is_cell_solid(&self, cell: IVec2) -> bool {
+        self.index(cell)
+            .is_some_and(|index| self.cells[index / 32] & (1 << (index % 32)) != 0)
+    }
+
+    /// the cell containing a world position
+    pub fn cell_at(&self, position: Vec2) -> IVec2 {
+        ((position - self.origin) / self.cell_size)
+            .floor()
+            .as_ivec2()
+    }
+
+    fn index(&self, cell: IVec2) -> Option<usize> {
+        (cell.cmpge(IVec2::ZERO).all() && cell.cmplt(self.size.as_ivec2()).all())
+            .then(|| (cell.y as u32 * self.size.x + cell.x as u32) as usize)
+    }
+}
+
+impl ParticleCollisionQuery for ParticleCollisionGrid {
+    fn is_solid(&self, position: Vec2) -> bool {
+        self.is_cell_solid(self.cell_at(position))
+    }
+
+    fn normal(&self, previous: Vec2, position: Vec2) -> Vec2 {
+        let from = self.cell_at(previous);
+        let step = self.cell_at(position) - from;
+
+        // faces of the entered cells, that point back to the particle
+        let mut normal = Vec2::ZERO;
+        if step.x != 0 && self.is_cell_solid(from + IVec2::new(step.x, 0)) {
+            normal.x = -step.x.signum() as f32;
+        }
+        if step.y != 0 && self.is_cell_solid(from + IVec2::new(0, step.y)) {
+            normal.y = -step.y.signum() as f32;
+        }
+        if normal == Vec2::ZERO {
+            normal = -step.as_vec2();
+        }
+        normal
+            .try_normalize()
+            .or((previous - position).try_normalize())
+            .unwrap_or(Vec2::Y)
+    }
+}
+
+/// colliders of this frame, in world space
+#[derive(Resource, Default)]
+pub(crate) struct ParticleColliders {
+    pub(crate) shapes: Vec<WorldCollider>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct WorldCollider {
+    shape: ColliderShape,
+    position: Vec2,
+    rotation: Rot2,
+}
+
+/// everything particles can collide with, during an update
+#[derive(Default, Clone, Copy)]
+pub(crate) struct Colliders<'a> {
+    pub(crate) shapes: &'a [WorldCollider],
+    pub(crate) queries: &'a [&'a dyn ParticleCollisionQuery],
+}
+
+impl<'a> Colliders<'a> {
+    pub(crate) fn new(
+        colliders: &'a ParticleColliders,
+        queries: &'a [&'a dyn ParticleCollisionQuery],
+    ) -> Self {
+        Self {
+            shapes: &colliders.shapes,
+            queries,
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.shapes.is_empty() && self.queries.is_empty()
+    }
+
+    /// first surface crossed from `previous` to `position`
+    fn hit(&self, previous: Vec2, position: Vec2) -> Option<Hit> {
+        self.shapes
+            .iter()
+            .find_map(|collider| collider.hit(previous, position))
+            .or_else(|| {
+                // queries only know solid positions, the particle goes back to where it came from
+                self.queries
+                    .iter()
+                    .find(|query| query.is_solid(position))
+                    .map(|query| Hit {
+                        point: previous,
+                        normal: query.normal(previous, position),
+                    })
+            })
+    }
+}
+
+/// point of contact and surface normal
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Hit {
+    pub(crate) point: Vec2,
+    pub(crate) normal: Vec2,
+}
+
+impl WorldCollider {
+    pub(crate) fn new(shape: ColliderShape, position: Vec2, angle: f32) -> Self {
+        Self {
+            shape,
+            position,
+            rotation: Rot2::radians(angle),
+        }
+    }
+
+    /// checks the movement of a particle from `previous` to `position`
+    fn hit(&self, previous: Vec2, position: Vec2) -> Option<Hit> {
+        let local = self.rotation.inverse() * (position - self.position);
+        let from = self.rotation.inverse() * (previous - self.position);
+        let path = local - from;
+        let to_world = |point: Vec2, normal: Vec2| Hit {
+            point: self.position + self.rotation * point,
+            normal: self.rotation * normal,
+        };
+
+        match &self.shape {
+            ColliderShape::HalfPlane { normal } => {
+                let normal = normal.normalize_or_zero();
+                let depth = local.dot(normal);
+                (depth < 0.).then(|| to_world(local - normal * depth, normal))
+            }
+            ColliderShape::Circle(radius) => {
+                if from.length() < *radius {
+                    // started inside, leave the closest way
+                    if local.length() >= *radius {
+                        return None;
+                    }
+                    let normal = local
+                        .try_normalize()
+                        .or(from.try_normalize())
+                        .unwrap_or(Vec2::Y);
+                    return Some(to_world(normal * *radius, normal));
+                }
+
+                // first intersection of the path with the circle
+                let a = path.length_squared();
+                let b = from.dot(path);
+                let c = from.length_squared() - radius * radius;
+                let discriminant = b * b - a * c;
+                if a <= f32::EPSILON || discriminant < 0. {
+                    return None;
+                }
+                let t = (-b - discriminant.sqrt()) / a;
+                (0. ..=1.).contains(&t).then(|| {
+                    let point = from + path * t;
+                    to_world(point, point.normalize_or_zero())
+                })
+            }
+            ColliderShape::Rectangle { half_size } => {
+                let margin = *half_size - from.abs();
+                if margin.min_element() > 0. {
+                    // started inside, leave on the closest side
+                    let margin = *half_size - local.abs();
+                    if margin.min_element() <= 0. {
+                        return None;
+                    }
+                    let normal = if margin.x < margin.y {
+                        Vec2::X * local.x.signum()
+                    } else {
+                        Vec2::Y * local.y.signum()
+                    };
+                    return Some(to_world(local + normal * margin.min_element(), normal));
+                }
+
+                // slab test, the path enters the box on the axis it crosses last
+                let (mut enter, mut exit, mut normal) = (0_f32, 1_f32, Vec2::ZERO);
+                for axis in 0..2 {
+                    if path[axis].abs() <= f32::EPSILON {
+                        if from[axis].abs() >= half_size[axis] {
+                            return None;
+                        }
+                        continue;
+                    }
+                    let near = (-half_size[axis] * path[axis].signum() - from[axis]) / path[axis];
+                    let far = (half_size[axis] * path[axis].signum() - from[axis]) / path[axis];
+                    if near > enter {
+                        enter = near;
+                        normal = Vec2::ZERO;
+                        normal[axis] = -path[axis].signum();
+                    }
+                    exit = exit.min(far);
+                }
+                (normal != Vec2::ZERO && enter <= exit)
+                    .then(|| to_world(from + path * enter, normal))
+            }
+            ColliderShape::Polyline(points) => points
+                .windows(2)
+                .filter_map(|segment| {
+                    let (start, end) = (segment[0], segment[1]);
+                    let edge = end - start;
+                    let denominator = path.perp_dot(edge);
+                    if denominator.abs() <= f32::EPSILON {
+                        return None;
+                    }
+                    let t = (start - from).perp_dot(edge) / denominator;
+                    let u = (start - from).perp_dot(path) / denominator;
+                    ((0. ..=1.).contains(&t) && (0. ..=1.).contains(&u)).then(|| {
+                        let mut normal = edge.perp().normalize_or_zero();
+                        if normal.dot(path) > 0. {
+                            normal = -normal;
+                        }
+                        (t, from + path * t, normal)
+                    })
+                })
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|(_, point, normal)| to_world(point, normal)),
+        }
+    }
+}
+
+pub(crate) fn collect_colliders(
+    colliders: Query<(&ParticleCollider2d, &GlobalTransform)>,
+    mut collected: ResMut<ParticleColliders>,
+) {
+    collected.shapes.clear();
+    collected
+        .shapes
+        .extend(colliders.iter().map(|(collider, transform)| {
+            WorldCollider::new(
+                collider.0.clone(),
+                transform.translation().truncate(),
+                transform.rotation().to_euler(EulerRot::XYZ).2,
+            )
+        }));
+}
+
+/// resolves particles, that moved into a collider since `previous`.
+/// Returns the index and surface normal of every collided particle.
+pub(crate) fn collide_particles(
+    particles: &mut ParticleStore,
+    response: CollisionResponse,
+    colliders: Colliders,
+    previous: &(Vec<f32>, Vec<f32>),
+) -> Vec<(usize, Vec2)> {
+    let mut collided = Vec::new();
+    for index in 0..previous.0.len().min(particles.len()) {
+        let from = Vec2::new(previous.0[index], previous.1[index]);
+        let position = Vec2::new(particles.position_x[index], particles.position_y[index]);
+        let Some(hit) = colliders.hit(from, position) else {
+            continue;
+        };
+
+        resolve(particles, index, response, hit);
+        collided.push((index, hit.normal));
+    }
+    collided
+}
+
+fn resolve(particles: &mut ParticleStore, index: usize, response: CollisionResponse, hit: Hit) {
+    match response {
+        CollisionResponse::Die => {
+            particles.duration_fraction[index] = 1.;
+        }
+        CollisionResponse::Stick => {
+            particles.position_x[index] = hit.point.x;
+            particles.position_y[index] = hit.point.y;
+            particles.velocity_x[index] = 0.;
+            particles.velocity_y[index] = 0.;
+            particles.angular_velocity[index] = 0.;
+            particles.gravity_speed[index] = 0.;
+        }
+        CollisionResponse::Bounce {
+            restitution,
+            friction,
+        } => {
+            // rest a bit above the surface, so the next frame starts outside
+            let point = hit.point + hit.normal * 0.01;
+            particles.position_x[index] = point.x;
+            particles.position_y[index] = point.y;
+
+            let velocity = Vec2::new(particles.velocity_x[index], particles.velocity_y[index]);
+            let speed = velocity.dot(hit.normal);
+            if speed < 0. {
+                let normal = hit.normal * speed;
+                let tangent = velocity - normal;
+                let velocity = tangent * (1. - friction) - normal * restitution;
+                particles.velocity_x[index] = velocity.x;
+                particles.velocity_y[index] = velocity.y;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::system::Res;
+
+    fn store(position: Vec2, velocity: Vec2) -> ParticleStore {
+        let mut store = ParticleStore::default();
+        store.position_x.push(position.x);
+        store.position_y.push(position.y);
+        store.velocity_x.push(velocity.x);
+        store.velocity_y.push(velocity.y);
+        store.angular_velocity.push(1.);
+        store.gravity_speed.push(1.);
+        store.duration.push(1.);
+        store.duration_fraction.push(0.);
+        store
+    }
+
+    #[test]
+    fn shapes_report_surface_hits() {
+        let ground = WorldCollider::new(
+            ColliderShape::HalfPlane { normal: Vec2::Y },
+            Vec2::new(0., -10.),
+            0.,
+        );
+        let hit = ground.hit(Vec2::ZERO, Vec2::new(3., -12.)).unwrap();
+        assert!(hit.point.abs_diff_eq(Vec2::new(3., -10.), 1e-5));
+        assert!(hit.normal.abs_diff_eq(Vec2::Y, 1e-5));
+        assert_eq!(ground.hit(Vec2::ZERO, Vec2::new(3., -9.)), None);
+
+        let circle = WorldCollider::new(ColliderShape::Circle(5.), Vec2::ZERO, 0.);
+        let hit = circle.hit(Vec2::new(10., 0.), Vec2::new(4., 0.)).unwrap();
+        assert!(hit.point.abs_diff_eq(Vec2::new(5., 0.), 1e-5));
+        // fast particles pass through in a single frame
+        let hit = circle.hit(Vec2::new(10., 3.), Vec2::new(-10., 3.)).unwrap();
+        assert!(hit.point.abs_diff_eq(Vec2::new(4., 3.), 1e-5), "{:?}", hit);
+        assert!(hit.normal.abs_diff_eq(Vec2::new(0.8, 0.6), 1e-5));
+        assert_eq!(circle.hit(Vec2::new(10., 6.), Vec2::new(-10., 6.)), None);
+
+        // rotated by 90°, the wide box is upright
+        let wall = WorldCollider::new(
+            ColliderShape::Rectangle {
+                half_size: Vec2::new(10., 1.),
+            },
+            Vec2::ZERO,
+            std::f32::consts::FRAC_PI_2,
+        );
+        let hit = wall.hit(Vec2::new(-5., 0.), Vec2::new(-0.5, 8.)).unwrap();
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_X, 1e-5), "{:?}", hit);
+        assert!(
+            hit.point.abs_diff_eq(Vec2::new(-1., 64. / 9.), 1e-5),
+            "{:?}",
+            hit
+        );
+        let hit = wall.hit(Vec2::new(-5., 2.), Vec2::new(5., 2.)).unwrap();
+        assert!(hit.normal.abs_diff_eq(Vec2::NEG_X, 1e-5), "{:?}", hit);
+        assert!(hit.point.abs_diff_eq(Vec2::new(-1., 2.), 1e-5), "{:?}", hit);
+        assert_eq!(wall.hit(Vec2::new(-5., 12.), Vec2::new(5., 12.)), None);
+
+        let line = WorldCollider::new(
+            ColliderShape::Polyline(vec![Vec2::new(-10., 0.), Vec2::new(10., 0.)]),
+            Vec2::ZERO,
+            0.,
+        );
+        let hit = line.hit(Vec2::new(0., 5.), Vec2::new(0., -5.)).unwrap();
+        assert!(hit.point.abs_diff_eq(Vec2::ZERO, 1e-5));
+        assert!(hit.normal.abs_diff_eq(Vec2::Y, 1e-5));
+        assert_eq!(line.hit(Vec2::new(20., 5.), Vec2::new(20., -5.)), None);
+    }
+
+    #[test]
+    fn responses_bounce_stick_and_die() {
+        let shapes = [WorldCollider::new(
+            ColliderShape::HalfPlane { normal: Vec2::Y },
+            Vec2::ZERO,
+            0.,
+        )];
+        let colliders = Colliders {
+            shapes: &shapes,
+            ..Default::default()
+        };
+        let previous = (vec![0.], vec![1.]);
+
+        let mut particles = store(Vec2::new(0., -1.), Vec2::new(4., -10.));
+        let bounce = CollisionResponse::Bounce {
+            restitution: 0.5,
+            friction: 0.25,
+        };
+        collide_particles(&mut particles, bounce, colliders, &previous);
+        assert!(particles.position_y[0] > 0.);
+        assert_eq!((particles.velocity_x[0], particles.velocity_y[0]), (3., 5.));
+
+        let mut particles = store(Vec2::new(0., -1.), Vec2::new(4., -10.));
+        collide_particles(
+            &mut particles,
+            CollisionResponse::Stick,
+            colliders,
+            &previous,
+        );
+        assert_eq!(particles.position_y[0], 0.);
+        assert_eq!(particles.velocity_y[0], 0.);
+        assert_eq!(particles.gravity_speed[0], 0.);
+
+        let mut particles = store(Vec2::new(0., -1.), Vec2::new(4., -10.));
+        collide_particles(&mut particles, CollisionResponse::Die, colliders, &previous);
+        assert_eq!(particles.duration_fraction[0], 1.);
+    }
+
+    #[test]
+    fn grid_cells_are_solid_with_face_normals() {
+        let mut grid =
+            ParticleCollisionGrid::new(UVec2::new(40, 3), Vec2::splat(16.), Vec2::new(-32., 0.));
+        grid.set(UVec2::new(2, 0), true);
+        grid.set(UVec2::new(39, 2), true);
+        grid.set(UVec2::new(40, 0), true);
+
+        assert!(grid.is_solid(Vec2::new(8., 8.)));
+        assert!(grid.is_solid(Vec2::new(-32. + 39. * 16. + 1., 40.)));
+        assert!(!grid.is_solid(Vec2::new(-8., 8.)));
+        assert!(!grid.is_solid(Vec2::new(8., -8.)), "outside of the grid");
+        assert!(!grid.is_cell_solid(IVec2::new(40, 0)));
+
+        // entering from the left and from above
+        let normal = grid.normal(Vec2::new(-4., 8.), Vec2::new(4., 6.));
+        assert_eq!(normal, Vec2::NEG_X);
+        let normal = grid.normal(Vec2::new(8., 20.), Vec2::new(9., 12.));
+        assert_eq!(normal, Vec2::Y);
+
+        let queries: [&dyn ParticleCollisionQuery; 1] = [&grid];
+        let colliders = Colliders {
+            queries: &queries,
+            ..Default::default()
+        };
+        let mut particles = store(Vec2::new(4., 6.), Vec2::new(10., -2.));
+        let previous = (vec![-4.], vec![8.]);
+        let bounce = CollisionResponse::Bounce {
+            restitution: 1.,
+            friction: 0.,
+        };
+        collide_particles(&mut particles, bounce, colliders, &previous);
+        assert!(particles.position_x[0] < 0.);
+        assert_eq!(
+            (particles.velocity_x[0], particles.velocity_y[0]),
+            (-10., -2.)
+        );
+    }
+
+    fn solid_center(queries: Res<ParticleCollisionQueries>, resources: FilteredResources) -> bool {
+        let queries = queries.collect(&resources);
+        queries.len() == 1 && queries[0].is_solid(Vec2::splat(0.5))
+    }
+
+    #[test]
+    fn queries_are_read_in_place() {
+        use bevy_ecs::{
+            system::{
+                FilteredResourcesParamBuilder, ParamBuilder, RunSystemOnce, SystemParamBuilder,
+            },
+            world::World,
+        };
+
+        let mut world = World::new();
+        let mut queries = ParticleCollisionQueries::default();
+        queries.register::<ParticleCollisionGrid>();
+        queries.register::<ParticleCollisionGrid>();
+        assert_eq!(queries.0.len(), 1);
+
+        let mut grid = ParticleCollisionGrid::new(UVec2::ONE, Vec2::ONE, Vec2::ZERO);
+        grid.set(UVec2::ZERO, true);
+        world.insert_resource(grid);
+        world.insert_resource(queries.clone());
+
+        let system = (
+            ParamBuilder,
+            FilteredResourcesParamBuilder::new(move |builder| queries.add_reads(builder)),
+        )
+            .build_state(&mut world)
+            .build_system(solid_center);
+        assert!(world.run_system_once(system).unwrap());
+    }
+}
@@ -4,6 +4,7 @@ use bevy_math::{
     Curve, FloatExt,
 };
 use serde::{Deserialize, Serialize};
+use wide::f32x8;
 
 #[derive(Deserialize, Default, Serialize, Debug, Clone)]
 pub struct MultiCurve<T>
@@ -83,6 +84,69 @@ where
     }
 }
 
+/// linear steps an eased segment is flattened into
+const EASED_STEPS: usize = 16;
+
+/// a float curve flattened into linear knots, to sample 8 positions at once.
+/// Linear segments are exact, eased segments are split into [`EASED_STEPS`].
+pub(crate) struct CurveKnots {
+    knots: Vec<(f32, f32)>,
+    start_value: f32,
+    end_value: f32,
+}
+
+impl CurveKnots {
+    pub(crate) fn new(curve: &MultiCurve<f32>) -> Self {
+        let mut knots: Vec<(f32, f32)> = Vec::with_capacity(curve.points.len());
+        for (value, position, easing) in curve.points.iter() {
+            if let (Some(_), Some(&(left, _))) = (easing, knots.last()) {
+                for step in 1..EASED_STEPS {
+                    let x = left.lerp(*position, step as f32 / EASED_STEPS as f32);
+                    knots.push((x, curve.lerp(x)));
+                }
+            }
+            knots.push((*position, *value));
+        }
+        Self {
+            knots,
+            start_value: curve.start_value,
+            end_value: curve.end_value,
+        }
+    }
+
+    /// reads the values of 8 positions, like [`MultiCurve::lerp`]
+    pub(crate) fn sample8(&self, position: f32x8) -> f32x8 {
+        let (Some(first), Some(last)) = (self.knots.first(), self.knots.last()) else {
+            return f32x8::splat(self.end_value);
+        };
+        let position = position.max(f32x8::ZERO);
+
+        // sums the rise of every segment the position has passed
+        let mut value = f32x8::splat(first.1);
+        for pair in self.knots.windows(2) {
+            let ((left, left_value), (right, right_value)) = (pair[0], pair[1]);
+            let progress = if right > left {
+                ((position - f32x8::splat(left)) * f32x8::splat((right - left).recip()))
+                    .max(f32x8::ZERO)
+                    .min(f32x8::ONE)
+            } else {
+                // points on the same position jump once it is reached
+                position
+                    .simd_ge(f32x8::splat(right))
+                    .select(f32x8::ONE, f32x8::ZERO)
+            };
+            value += progress * f32x8::splat(right_value - left_value);
+        }
+
+        let value = position
+            .simd_lt(f32x8::splat(first.0))
+            .select(f32x8::splat(self.start_value), value);
+        position
+            .simd_ge(f32x8::splat(last.0))
+            .select(f32x8::splat(self.end_value), value)
+    }
+}
+
 pub trait LerpThat<T> {
     fn lerp_that(self, right: T, val: f32) -> T;
 }
@@ -0,0 +1,363 @@
+use crate::{
+    update::{load8, store8, ParticleStore},
+    Falloff,
+};
+use bevy_ecs::{
+    component::Component,
+    reflect::ReflectComponent,
+    resource::Resource,
+    system::{Query, ResMut},
+};
+use bevy_math::{EulerRot, Rot2, Vec2};
+use bevy_reflect::{prelude::ReflectDefault, Reflect};
+use bevy_transform::components::{GlobalTransform, Transform};
+use wide::f32x8;
+
+/// A force in the world, that affects the particles of every
+/// spawner with a matching `ParticleForceLayers`.
+#[derive(Component, Clone, Debug, Reflect)]
+#[reflect(Component, Debug, Default)]
+#[require(Transform)]
+pub struct ParticleForceField2d {
+    pub force: ForceKind,
+    /// area of influence, rotated with the entity
+    pub shape: ForceFieldShape,
+    /// how the force fades from the center to the edge of the shape.
+    /// `Inverse` and `InverseSquare` use the distance in world units.
+    pub falloff: Falloff,
+    /// bit mask, matched against `ParticleForceLayers`
+    pub layers: u32,
+}
+
+impl Default for ParticleForceField2d {
+    fn default() -> Self {
+        Self::new(ForceKind::Point { strength: 1000. })
+    }
+}
+
+impl ParticleForceField2d {
+    /// infinite field with constant falloff on the first layer
+    pub fn new(force: ForceKind) -> Self {
+        Self {
+            force,
+            shape: ForceFieldShape::Infinite,
+            falloff: Falloff::Constant,
+            layers: 1,
+        }
+    }
+
+    pub fn with_shape(mut self, shape: ForceFieldShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    pub fn with_layers(mut self, layers: u32) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// strength multiplier at 8 local positions, zero outside of the shape
+    fn weight8(&self, x: f32x8, y: f32x8) -> f32x8 {
+        let (zero, one) = (f32x8::ZERO, f32x8::ONE);
+        let distance_squared = x * x + y * y;
+        // 0 at the center, 1 at the edge
+        let edge = match self.shape {
+            ForceFieldShape::Infinite => zero,
+            ForceFieldShape::Circle(radius) => distance_squared.sqrt() / f32x8::splat(radius),
+            ForceFieldShape::Rectangle { half_size } => {
+                (x.abs() / f32x8::splat(half_size.x)).max(y.abs() / f32x8::splat(half_size.y))
+            }
+        };
+
+        let weight = match self.falloff {
+            Falloff::Constant => one,
+            Falloff::Linear => one - edge,
+            Falloff::Inverse => one / distance_squared.sqrt().max(one),
+            Falloff::InverseSquare => one / distance_squared.max(one),
+            Falloff::Smoothstep => one - edge * edge * (f32x8::splat(3.) - edge - edge),
+        };
+        edge.simd_gt(one).select(zero, weight)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum ForceKind {
+    /// pulls towards the center, a negative strength pushes away
+    Point { strength: f32 },
+    /// constant acceleration, rotated with the entity
+    Wind { force: Vec2 },
+    /// orbits the center, counter clockwise for a positive strength
+    Vortex { strength: f32 },
+    /// fraction of the velocity lost per second
+    Drag { coefficient: f32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum ForceFieldShape {
+    Infinite,
+    Circle(f32),
+    Rectangle { half_size: Vec2 },
+}
+
+/// Opts a spawner into force fields, with any shared layer bit.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Component, Debug, Default)]
+pub struct ParticleForceLayers(pub u32);
+
+impl ParticleForceLayers {
+    pub const ALL: Self = Self(u32::MAX);
+}
+
+impl Default for ParticleForceLayers {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// force fields of this frame, in world space
+#[derive(Resource, Default)]
+pub(crate) struct ParticleForceFields(pub(crate) Vec<WorldForceField>);
+
+#[derive(Clone, Debug)]
+pub(crate) struct WorldForceField {
+    field: ParticleForceField2d,
+    position: Vec2,
+    rotation: Rot2,
+}
+
+impl WorldForceField {
+    /// velocity of 8 particles after `delta` seconds in the field
+    fn velocity8(&self, x: f32x8, y: f32x8, vx: f32x8, vy: f32x8, delta: f32) -> (f32x8, f32x8) {
+        let zero = f32x8::ZERO;
+        let ox = x - f32x8::splat(self.position.x);
+        let oy = y - f32x8::splat(self.position.y);
+        // the shape is rotated with the entity
+        let (cos, sin) = (
+            f32x8::splat(self.rotation.cos),
+            f32x8::splat(self.rotation.sin),
+        );
+        let weight =
+            self.field.weight8(cos * ox + sin * oy, cos * oy - sin * ox) * f32x8::splat(delta);
+
+        let distance = (ox * ox + oy * oy).sqrt();
+        let non_zero = distance.simd_gt(zero);
+        let safe_distance = non_zero.select(distance, f32x8::ONE);
+        let nx = non_zero.select(ox / safe_distance, zero);
+        let ny = non_zero.select(oy / safe_distance, zero);
+
+        let (ax, ay) = match self.field.force {
+            ForceKind::Point { strength } => {
+                let strength = f32x8::splat(-strength);
+                (nx * strength, ny * strength)
+            }
+            ForceKind::Wind { force } => {
+                let force = self.rotation * force;
+                (f32x8::splat(force.x), f32x8::splat(force.y))
+            }
+            ForceKind::Vortex { strength } => {
+                let strength = f32x8::splat(strength);
+                (-ny * strength, nx * strength)
+            }
+            ForceKind::Drag { coefficient } => {
+                let drag = (f32x8::ONE - f32x8::splat(coefficient) * weight).max(zero);
+                return (vx * drag, vy * drag);
+            }
+        };
+        (vx + ax * weight, vy + ay * weight)
+    }
+
+    /// velocity of a particle, same as one lane of `velocity8`
+    fn velocity(&self, position: Vec2, velocity: Vec2, delta: f32) -> Vec2 {
+        let (x, y) = self.velocity8(
+            f32x8::splat(position.x),
+            f32x8::splat(position.y),
+            f32x8::splat(velocity.x),
+            f32x8::splat(velocity.y),
+            delta,
+        );
+        Vec2::new(x.to_array()[0], y.to_array()[0])
+    }
+}
+
+pub(crate) fn collect_force_fields(
+    fields: Query<(&ParticleForceField2d, &GlobalTransform)>,
+    mut collected: ResMut<ParticleForceFields>,
+) {
+    collected.0.clear();
+    collected
+        .0
+        .extend(fields.iter().map(|(field, transform)| WorldForceField {
+            field: field.clone(),
+            position: transform.translation().truncate(),
+            rotation: Rot2::radians(transform.rotation().to_euler(EulerRot::XYZ).2),
+        }));
+}
+
+/// accelerates the particles of a spawner, by every field on its layers
+pub(crate) fn apply_force_fields(
+    particles: &mut ParticleStore,
+    fields: &[WorldForceField],
+    layers: ParticleForceLayers,
+    delta: f32,
+) {
+    let simd_len = particles.len() / 8 * 8;
+    for world in fields
+        .iter()
+        .filter(|world| world.field.layers & layers.0 != 0)
+    {
+        for index in (0..simd_len).step_by(8) {
+            let (vx, vy) = world.velocity8(
+                load8(&particles.position_x, index),
+                load8(&particles.position_y, index),
+                load8(&particles.velocity_x, index),
+                load8(&particles.velocity_y, index),
+                delta,
+            );
+            store8(&mut particles.velocity_x, index, vx);
+            store8(&mut particles.velocity_y, index, vy);
+        }
+
+        for index in simd_len..particles.len() {
+            let velocity = world.velocity(
+                Vec2::new(particles.position_x[index], particles.position_y[index]),
+                Vec2::new(particles.velocity_x[index], particles.velocity_y[index]),
+                delta,
+            );
+            particles.velocity_x[index] = velocity.x;
+            particles.velocity_y[index] = velocity.y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(positions: &[Vec2]) -> ParticleStore {
+        let mut store = ParticleStore::default();
+        for position in positions {
+            store.position_x.push(position.x);
+            store.position_y.push(position.y);
+            store.velocity_x.push(0.);
+            store.velocity_y.push(0.);
+            store.duration.push(1.);
+        }
+        store
+    }
+
+    fn world(field: ParticleForceField2d, position: Vec2, angle: f32) -> WorldForceField {
+        WorldForceField {
+            field,
+            position,
+            rotation: Rot2::radians(angle),
+        }
+    }
+
+    #[test]
+    fn fields_respect_shape_rotation_and_layers() {
+        let wind = ParticleForceField2d::new(ForceKind::Wind { force: Vec2::X })
+            .with_shape(ForceFieldShape::Rectangle {
+                half_size: Vec2::new(10., 2.),
+            })
+            .with_layers(0b10);
+        // rotated by 90°, the wind blows up and the box stands upright
+        let fields = [world(
+            wind,
+            Vec2::new(100., 0.),
+            std::f32::consts::FRAC_PI_2,
+        )];
+
+        let mut particles = store(&[Vec2::new(100., 8.), Vec2::new(108., 0.)]);
+        apply_force_fields(&mut particles, &fields, ParticleForceLayers(1), 1.);
+        assert_eq!(particles.velocity_y, [0., 0.], "other layer");
+
+        apply_force_fields(&mut particles, &fields, ParticleForceLayers::ALL, 1.);
+        assert!((particles.velocity_y[0] - 1.).abs() < 1e-5);
+        assert!(particles.velocity_x[0].abs() < 1e-5);
+        assert_eq!(particles.velocity_y[1], 0., "outside of the box");
+    }
+
+    #[test]
+    fn point_vortex_and_drag() {
+        let fields = [
+            world(
+                ParticleForceField2d::new(ForceKind::Point { strength: 10. })
+                    .with_shape(ForceFieldShape::Circle(20.))
+                    .with_falloff(Falloff::Linear),
+                Vec2::ZERO,
+                0.,
+            ),
+            world(
+                ParticleForceField2d::new(ForceKind::Vortex { strength: 4. }),
+                Vec2::ZERO,
+                0.,
+            ),
+        ];
+        let mut particles = store(&[Vec2::new(10., 0.)]);
+        apply_force_fields(&mut particles, &fields, ParticleForceLayers::default(), 1.);
+        assert!((particles.velocity_x[0] + 5.).abs() < 1e-5);
+        assert!((particles.velocity_y[0] - 4.).abs() < 1e-5);
+
+        let drag = [world(
+            ParticleForceField2d::new(ForceKind::Drag { coefficient: 2. }),
+            Vec2::ZERO,
+            0.,
+        )];
+        apply_force_fields(&mut particles, &drag, ParticleForceLayers::default(), 0.25);
+        assert!((particles.velocity_x[0] + 2.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn simd_chunks_match_single_particles() {
+        let fields = [
+            world(
+                ParticleForceField2d::new(ForceKind::Point { strength: 30. })
+                    .with_shape(ForceFieldShape::Circle(40.))
+                    .with_falloff(Falloff::Smoothstep),
+                Vec2::new(5., 5.),
+                0.,
+            ),
+            world(
+                ParticleForceField2d::new(ForceKind::Wind {
+                    force: Vec2::new(3., 1.),
+                })
+                .with_shape(ForceFieldShape::Rectangle {
+                    half_size: Vec2::new(30., 10.),
+                }),
+                Vec2::ZERO,
+                0.7,
+            ),
+            world(
+                ParticleForceField2d::new(ForceKind::Vortex { strength: 8. })
+                    .with_falloff(Falloff::InverseSquare),
+                Vec2::new(-10., 0.),
+                0.,
+            ),
+            world(
+                ParticleForceField2d::new(ForceKind::Drag { coefficient: 0.5 })
+                    .with_falloff(Falloff::Inverse),
+                Vec2::ZERO,
+                0.,
+            ),
+        ];
+        let positions: Vec<Vec2> = (0..11)
+            .map(|index| Vec2::from_angle(index as f32) * index as f32 * 4.)
+            .collect();
+        let mut particles = store(&positions);
+        particles.velocity_x.fill(2.);
+        apply_force_fields(&mut particles, &fields, ParticleForceLayers::ALL, 0.1);
+
+        for (index, position) in positions.into_iter().enumerate() {
+            let mut single = store(&[position]);
+            single.velocity_x[0] = 2.;
+            apply_force_fields(&mut single, &fields, ParticleForceLayers::ALL, 0.1);
+            assert!((particles.velocity_x[index] - single.velocity_x[0]).abs() < 1e-5);
+            assert!((particles.velocity_y[index] - single.velocity_y[0]).abs() < 1e-5);
+        }
+    }
+}
@@ -17,36 +17,66 @@ use bevy_derive::{Deref, DerefMut};
 use bevy_ecs::{
     component::Component,
     schedule::{common_conditions::on_message, IntoScheduleConfigs},
+    system::{FilteredResourcesParamBuilder, ParamBuilder, SystemParamBuilder},
 };
-use bevy_math::Vec2;
+use bevy_image::TextureAtlasPlugin;
+use bevy_math::{Vec2, Vec3};
 use bevy_reflect::{Reflect, TypePath};
 use bevy_render::sync_world::SyncToRenderWorld;
 use bevy_shader::Shader;
 use bevy_transform::components::Transform;
 use color::ColorParticle2dMaterial;
 use serde::{Deserialize, Serialize};
-use values::Rval;
+use shape::DirectionMode;
+use values::{Rdist, Rval};
+use wide::f32x8;
 
+mod collision;
 mod color;
 mod curve;
+mod force;
 mod loader;
 mod material;
+mod messages;
+mod noise;
+mod rng;
+mod shape;
 mod sprite;
+mod sub_emitter;
+mod trail;
 mod update;
 mod values;
 
+pub use shape::EmissionShape;
+
 #[allow(unused)]
 pub mod prelude {
+    pub use super::collision::{
+        ColliderShape, CollisionResponse, ParticleCollider2d, ParticleCollisionGrid,
+        ParticleCollisionQuery, ParticleCollisionQueryPlugin,
+    };
     pub use super::color::ColorParticle2dMaterial;
     pub use super::curve::{LerpThat, MultiCurve /* , ParticleEaseFunction */};
+    pub use super::force::{ForceFieldShape, ForceKind, ParticleForceField2d, ParticleForceLayers};
     pub use super::loader::ParticleEffectLoader;
     pub use super::material::{Particle2dMaterial, Particle2dMaterialPlugin};
-    pub use super::sprite::SpriteParticle2dMaterial;
-    pub use super::update::{OneShot, ParticleEffectInstance, ParticleSpawnerState, ParticleStore};
-    pub use super::values::{Random, Rval};
+    pub use super::messages::{
+        ParticleCollided, ParticleDied, ParticleMessages, ParticleSnapshot, ParticlesSpawned,
+    };
+    pub use super::noise::{NoiseMode, Turbulence};
+    pub use super::rng::{ParticleRng, ParticleSeed};
+    pub use super::shape::{DirectionMode, EmissionPolygon, EmissionShape, ImageMask};
+    pub use super::sprite::{SpriteParticle2dMaterial, MAX_ATLAS_FRAMES};
+    pub use super::sub_emitter::{SubEmitter, SubEmitterOf, SubEmitterSpawners, SubEmitterTrigger};
+    pub use super::trail::{Trail, TrailUv};
+    pub use super::update::{
+        OneShot, OverflowPolicy, ParticleEffectInstance, ParticleSpawnerState, ParticleStore,
+    };
+    pub use super::values::{ColorDist, Random, Rdist, Rval};
     pub use super::{
-        Attractor, EmissionShape, EnokiPlugin, NoAutoAabb, Particle2dEffect, ParticleEffectHandle,
-        ParticleSpawner,
+        AnimationMode, Attractor, Burst, ColorCurveMode, CurveInput, EnokiPlugin, Falloff,
+        NoAutoAabb, Particle2dEffect, ParticleEffectHandle, ParticleSpawner, RotationMode,
+        SpriteAnimation, VelocityStretch,
     };
 }
 
@@ -54,6 +84,8 @@ pub(crate) const PARTICLE_VERTEX_OUT: Handle<Shader> =
     uuid_handle!("8d6bc2d4-7577-4890-a3a4-0faea3a27448");
 pub(crate) const PARTICLE_VERTEX: Handle<Shader> =
     uuid_handle!("57c98346-305c-461a-8cdc-7b3fac8be0ca");
+pub(crate) const PARTICLE_TRAIL_VERTEX: Handle<Shader> =
+    uuid_handle!("c2f0b0d5-4c1e-4a8e-9a51-6f0d8f3e2b17");
 pub(crate) const PARTICLE_COLOR_FRAG: Handle<Shader> =
     uuid_handle!("f60a0cf3-19d3-4425-b6f8-b06bf7ba2f34");
 pub(crate) const PARTICLE_SPRITE_FRAG: Handle<Shader> =
@@ -76,6 +108,13 @@ impl Plugin for EnokiPlugin {
             Shader::from_wgsl
         );
 
+        load_internal_asset!(
+            app,
+            PARTICLE_TRAIL_VERTEX,
+            "shaders/particle_trail_vertex.wgsl",
+            Shader::from_wgsl
+        );
+
         load_internal_asset!(
             app,
             PARTICLE_COLOR_FRAG,
@@ -90,6 +129,9 @@ impl Plugin for EnokiPlugin {
             Shader::from_wgsl
         );
 
+        if !app.is_plugin_added::<TextureAtlasPlugin>() {
+            app.add_plugins(TextureAtlasPlugin);
+        }
         app.add_plugins(material::Particle2dMaterialPlugin::<SpriteParticle2dMaterial>::default());
         app.add_plugins(material::Particle2dMaterialPlugin::<ColorParticle2dMaterial>::default());
 
@@ -97,6 +139,22 @@ impl Plugin for EnokiPlugin {
         app.register_type::<update::ParticleSpawnerState>();
         app.register_type::<update::ParticleSpawnerState>();
         app.register_type::<ParticleEffectHandle>();
+        app.register_type::<rng::ParticleSeed>();
+        app.register_type::<force::ParticleForceField2d>();
+        app.register_type::<force::ParticleForceLayers>();
+        app.init_resource::<shape::ImageMaskCache>();
+        app.init_resource::<force::ParticleForceFields>();
+        app.register_type::<collision::ParticleCollider2d>();
+        app.init_resource::<collision::ParticleColliders>();
+        app.register_type::<collision::ParticleCollisionGrid>();
+        app.register_type::<messages::ParticleMessages>();
+        app.add_message::<messages::ParticlesSpawned>();
+        app.add_message::<messages::ParticleDied>();
+        app.add_message::<messages::ParticleCollided>();
+        app.init_resource::<collision::ParticleCollisionQueries>();
+        app.world_mut()
+            .resource_mut::<collision::ParticleCollisionQueries>()
+            .register::<collision::ParticleCollisionGrid>();
         app.init_asset::<Particle2dEffect>();
         app.init_asset_loader::<loader::ParticleEffectLoader>();
 
@@ -125,9 +183,19 @@ impl Plugin for EnokiPlugin {
             Update,
             (
                 loader::reload_effect,
+                sprite::sync_atlas_rects,
                 update::clone_effect,
                 update::remove_finished_spawner,
-                update::update_spawner,
+                update::prepare_image_masks.before(update::update_spawner),
+                update::apply_seed.before(update::update_spawner),
+                force::collect_force_fields.before(update::update_spawner),
+                collision::collect_colliders.before(update::update_spawner),
+                messages::apply_message_flags.before(update::update_spawner),
+                sub_emitter::follow_parents.before(update::update_spawner),
+                sub_emitter::load_sub_emitter_effects.before(update::update_spawner),
+                sub_emitter::emit_sub_particles.after(update::update_spawner),
+                // sends the births of sub emitter particles in the same frame
+                messages::send_particle_messages.after(sub_emitter::emit_sub_particles),
             ),
         );
 
@@ -139,6 +207,27 @@ impl Plugin for EnokiPlugin {
             ),
         );
     }
+
+    fn finish(&self, app: &mut App) {
+        // collision queries are known once every plugin is built,
+        // the spawner update reads them without copying
+        let queries = app
+            .world()
+            .resource::<collision::ParticleCollisionQueries>()
+            .clone();
+        let update_spawner = (
+            ParamBuilder,
+            ParamBuilder,
+            ParamBuilder,
+            ParamBuilder,
+            ParamBuilder,
+            FilteredResourcesParamBuilder::new(move |builder| queries.add_reads(builder)),
+            ParamBuilder,
+        )
+            .build_state(app.world_mut())
+            .build_system(update::update_spawner);
+        app.add_systems(Update, update_spawner);
+    }
 }
 
 /// adding this component will disabled auto
@@ -180,19 +269,263 @@ impl Default for ParticleSpawner<ColorParticle2dMaterial> {
     }
 }
 
-#[derive(Deserialize, Reflect, Default, Clone, Debug, Serialize, PartialEq)]
-#[reflect]
-pub enum EmissionShape {
-    #[default]
-    Point,
-    Circle(f32),
-}
-
+/// Pulls particles towards a point, relative to the spawner.
+/// A negative strength pushes them away, as a repulsor.
 #[derive(Deserialize, Serialize, Clone, Debug, Reflect)]
 pub struct Attractor {
     pub position: Vec2,
     pub strength: f32,
+    /// closer distances are treated as this distance
     pub min_distance: f32,
+    /// how the force changes with distance, defaults to `InverseSquare`
+    pub falloff: Option<Falloff>,
+    /// particles further away are not affected
+    pub radius: Option<f32>,
+    /// particles closer than this are removed
+    pub kill_radius: Option<f32>,
+}
+
+impl Attractor {
+    pub fn new(position: Vec2, strength: f32, min_distance: f32) -> Self {
+        Self {
+            position,
+            strength,
+            min_distance,
+            falloff: None,
+            radius: None,
+            kill_radius: None,
+        }
+    }
+
+    /// force at a squared distance, ignores the radius
+    pub(crate) fn force(&self, distance_squared: f32) -> f32 {
+        let distance = distance_squared.sqrt();
+        let radius = self.radius.unwrap_or(f32::MAX);
+        match self.falloff.unwrap_or_default() {
+            Falloff::Constant => self.strength,
+            Falloff::Linear => self.strength * (1. - distance / radius).max(0.),
+            Falloff::Inverse => self.strength / distance.max(self.min_distance),
+            Falloff::InverseSquare => {
+                self.strength / distance_squared.max(self.min_distance * self.min_distance)
+            }
+            Falloff::Smoothstep => {
+                let t = (distance / radius).clamp(0., 1.);
+                self.strength * (1. - t * t * (3. - 2. * t))
+            }
+        }
+    }
+
+    /// force at 8 squared distances, same as `force`
+    pub(crate) fn force8(&self, distance_squared: f32x8) -> f32x8 {
+        let distance = distance_squared.sqrt();
+        let strength = f32x8::splat(self.strength);
+        let radius = f32x8::splat(self.radius.unwrap_or(f32::MAX));
+        let min_distance = f32x8::splat(self.min_distance);
+        match self.falloff.unwrap_or_default() {
+            Falloff::Constant => strength,
+            Falloff::Linear => strength * (f32x8::ONE - distance / radius).max(f32x8::ZERO),
+            Falloff::Inverse => strength / distance.max(min_distance),
+            Falloff::InverseSquare => {
+                strength / distance_squared.max(f32x8::splat(self.min_distance * self.min_distance))
+            }
+            Falloff::Smoothstep => {
+                let t = (distance / radius).max(f32x8::ZERO).min(f32x8::ONE);
+                strength * (f32x8::ONE - t * t * (f32x8::splat(3.) - f32x8::splat(2.) * t))
+            }
+        }
+    }
+}
+
+/// Strength of an attractor or force field over distance
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum Falloff {
+    /// same strength everywhere
+    Constant,
+    /// fades out linear towards the radius
+    Linear,
+    /// strength / distance
+    Inverse,
+    /// strength / distance²
+    #[default]
+    InverseSquare,
+    /// fades out smoothly towards the radius
+    Smoothstep,
+}
+
+/// How the rotation of a particle changes over its lifetime.
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum RotationMode {
+    /// rotates with the angular speed
+    #[default]
+    Free,
+    /// faces the direction it travels, `offset` in radians
+    /// is added to the velocity angle. Ignores the angular speed.
+    AlignToVelocity { offset: f32 },
+}
+
+/// How the color curve applies to the birth color of a particle.
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum ColorCurveMode {
+    /// the curve color replaces the birth color
+    #[default]
+    Replace,
+    /// the curve color tints the birth color
+    Multiply,
+}
+
+/// The value a curve is sampled by.
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum CurveInput {
+    /// lifetime fraction, from birth (0) to death (1)
+    #[default]
+    Lifetime,
+    /// current speed, `min` is the start and `max` the end of the curve
+    Speed { min: f32, max: f32 },
+    /// distance from the spawner, `min` is the start and `max` the end of the curve
+    Distance { min: f32, max: f32 },
+}
+
+impl CurveInput {
+    /// curve position of the particle at `index`
+    pub(crate) fn position(&self, particles: &ParticleStore, index: usize, spawner: Vec3) -> f32 {
+        let (value, min, max) = match *self {
+            CurveInput::Lifetime => return particles.duration_fraction[index],
+            CurveInput::Speed { min, max } => (
+                Vec2::new(particles.velocity_x[index], particles.velocity_y[index]).length(),
+                min,
+                max,
+            ),
+            CurveInput::Distance { min, max } => (
+                Vec2::new(
+                    particles.position_x[index] - spawner.x,
+                    particles.position_y[index] - spawner.y,
+                )
+                .length(),
+                min,
+                max,
+            ),
+        };
+        ((value - min) / (max - min).max(f32::EPSILON)).clamp(0., 1.)
+    }
+}
+
+/// Stretches particles along their velocity, in world space.
+/// The rotation of the particle is kept.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct VelocityStretch {
+    /// added length per unit of speed, relative to the x scale
+    pub factor: f32,
+    /// maximum length multiplier, defaults to unlimited
+    pub max: Option<f32>,
+}
+
+impl VelocityStretch {
+    /// length multiplier for a speed
+    pub fn length(&self, speed: f32) -> f32 {
+        (1. + speed * self.factor).min(self.max.unwrap_or(f32::MAX))
+    }
+}
+
+/// Picks the sprite sheet frame of every particle,
+/// instead of playing the whole sheet over the lifetime.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct SpriteAnimation {
+    /// defaults to `Lifetime`
+    pub mode: Option<AnimationMode>,
+    /// first frame of the played range, defaults to 0
+    pub first_frame: Option<u32>,
+    /// frames in the played range
+    pub frames: u32,
+    /// start at a random frame of the range
+    pub random_start: Option<bool>,
+}
+
+impl SpriteAnimation {
+    pub fn new(frames: u32) -> Self {
+        Self {
+            mode: None,
+            first_frame: None,
+            frames,
+            random_start: None,
+        }
+    }
+
+    /// sheet frame of a particle, `start` is its offset into the range
+    pub(crate) fn frame(&self, start: u32, fraction: f32, duration: f32) -> u32 {
+        let frames = self.frames.max(1);
+        let step = |fps: f32| (fraction * duration * fps).max(0.) as u32;
+        let frame = match self.mode.unwrap_or_default() {
+            AnimationMode::Lifetime => ((fraction * frames as f32) as u32).min(frames - 1) + start,
+            AnimationMode::Fixed => start,
+            AnimationMode::Loop { fps } => start + step(fps),
+            AnimationMode::PingPong { fps } => {
+                let period = (frames * 2).saturating_sub(2).max(1);
+                let frame = (start + step(fps)) % period;
+                frame.min(period - frame)
+            }
+        };
+        self.first_frame.unwrap_or(0) + frame % frames
+    }
+}
+
+/// How the frames of a `SpriteAnimation` are played.
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum AnimationMode {
+    /// the range once over the lifetime
+    #[default]
+    Lifetime,
+    /// one frame for the whole lifetime, use with `random_start` for variety sheets
+    Fixed,
+    /// repeats the range with a frame rate, independent of the lifetime
+    Loop { fps: f32 },
+    /// plays the range forward and back with a frame rate
+    PingPong { fps: f32 },
+}
+
+/// A number of particles, fired at a fixed time of the emitter cycle.
+#[derive(Deserialize, Serialize, Clone, Debug, Reflect)]
+pub struct Burst {
+    /// seconds after the emitter started
+    pub time: f32,
+    pub count: u32,
+    /// how often the burst repeats, defaults to 1
+    pub cycles: Option<u32>,
+    /// seconds between repeats
+    pub interval: Option<f32>,
+    /// chance between 0 - 1 for each repeat to fire, defaults to 1
+    pub probability: Option<f32>,
+}
+
+impl Burst {
+    pub fn new(time: f32, count: u32) -> Self {
+        Self {
+            time,
+            count,
+            cycles: None,
+            interval: None,
+            probability: None,
+        }
+    }
+
+    /// calls `fire` with the time and count of every repeat in
+    /// between `from` (inclusive) and `to` seconds. `to` is
+    /// inclusive too with `include_end`, for the end of the last cycle.
+    pub(crate) fn fire<R: rand::Rng + ?Sized>(
+        &self,
+        from: f32,
+        to: f32,
+        include_end: bool,
+        rng: &mut R,
+        mut fire: impl FnMut(f32, u32),
+    ) {
+        let interval = self.interval.unwrap_or(0.);
+        let probability = self.probability.unwrap_or(1.);
+        (0..self.cycles.unwrap_or(1))
+            .map(|cycle| self.time + cycle as f32 * interval)
+            .filter(|time| *time >= from && (*time < to || include_end && *time <= to))
+            .filter(|_| probability >= 1. || rng.random::<f32>() < probability)
+            .for_each(|time| fire(time, self.count));
+    }
 }
 
 /// holds the effect asset. Changing the Asset, will
@@ -213,23 +546,82 @@ impl From<Handle<Particle2dEffect>> for ParticleEffectHandle {
 pub struct Particle2dEffect {
     pub spawn_rate: f32,
     pub spawn_amount: u32,
+    /// emit `spawn_amount` every n world units the spawner moved,
+    /// instead of every `spawn_rate` seconds
+    pub spawn_distance: Option<f32>,
+    /// continuous emission in particles per second,
+    /// instead of `spawn_amount` every `spawn_rate` seconds
+    pub spawn_per_second: Option<f32>,
     pub emission_shape: EmissionShape,
-    pub lifetime: Rval<f32>,
-    pub linear_speed: Option<Rval<f32>>,
-    pub linear_acceleration: Option<Rval<f32>>,
+    /// spawn only on the edge of the emission shape
+    pub emit_from_edge: Option<bool>,
+    pub lifetime: Rdist,
+    pub linear_speed: Option<Rdist>,
+    pub linear_acceleration: Option<Rdist>,
     pub direction: Option<Rval<Vec2>>,
-    pub angular_speed: Option<Rval<f32>>,
-    pub angular_acceleration: Option<Rval<f32>>,
-    pub scale: Option<Rval<f32>>,
+    /// derive the direction from the spawn position on the emission shape,
+    /// the randomness of `direction` is used as spread.
+    pub direction_mode: Option<DirectionMode>,
+    pub angular_speed: Option<Rdist>,
+    pub angular_acceleration: Option<Rdist>,
+    /// initial rotation in radians, added to the spawner rotation
+    pub rotation: Option<Rdist>,
+    pub rotation_mode: Option<RotationMode>,
+    pub scale: Option<Rdist>,
+    /// initial y scale, defaults to `scale`
+    pub scale_y: Option<Rdist>,
+    /// lengthens particles along their velocity
+    pub stretch: Option<VelocityStretch>,
     pub color: Option<LinearRgba>,
+    /// random birth color, instead of `color`
+    pub random_color: Option<values::ColorDist>,
     pub gravity_direction: Option<Rval<Vec2>>,
-    pub gravity_speed: Option<Rval<f32>>,
-    pub linear_damp: Option<Rval<f32>>,
-    pub angular_damp: Option<Rval<f32>>,
+    pub gravity_speed: Option<Rdist>,
+    pub linear_damp: Option<Rdist>,
+    pub angular_damp: Option<Rdist>,
+    /// acceleration away from the spawner, negative values pull towards it
+    pub radial_acceleration: Option<Rdist>,
+    /// acceleration around the spawner, counter clockwise for positive values
+    pub tangential_acceleration: Option<Rdist>,
     pub scale_curve: Option<curve::MultiCurve<f32>>,
+    /// y scale over the lifetime, defaults to `scale_curve`
+    pub scale_y_curve: Option<curve::MultiCurve<f32>>,
+    /// what `scale_curve` and `scale_y_curve` are sampled by, defaults to `Lifetime`
+    pub scale_curve_input: Option<CurveInput>,
     pub color_curve: Option<curve::MultiCurve<LinearRgba>>,
+    /// how `color_curve` applies to the birth color, defaults to `Replace`
+    pub color_curve_mode: Option<ColorCurveMode>,
+    /// what `color_curve` is sampled by, defaults to `Lifetime`
+    pub color_curve_input: Option<CurveInput>,
+    /// multiplies the linear speed over the lifetime
+    pub speed_curve: Option<curve::MultiCurve<f32>>,
+    /// maximum linear speed over the lifetime
+    pub max_speed_curve: Option<curve::MultiCurve<f32>>,
+    /// multiplies the angular speed over the lifetime
+    pub angular_speed_curve: Option<curve::MultiCurve<f32>>,
+    /// drag over the lifetime, the fraction of velocity lost per second
+    pub damping_curve: Option<curve::MultiCurve<f32>>,
     pub attractors: Option<Vec<Attractor>>,
+    /// noise force for organic movement
+    pub turbulence: Option<noise::Turbulence>,
+    /// how particles react to `ParticleCollider2d` entities, no collision without it
+    pub collision: Option<collision::CollisionResponse>,
+    /// other effects, spawned at the particles of this one
+    pub sub_emitters: Option<Vec<sub_emitter::SubEmitter>>,
+    /// draws ribbons along the path of the particles, instead of quads
+    pub trail: Option<trail::Trail>,
+    /// sprite sheet frames of the particles, for the `SpriteParticle2dMaterial`
+    pub animation: Option<SpriteAnimation>,
     pub relative_positioning: Option<bool>,
+    /// seconds of one emitter cycle. Without looping, the spawner
+    /// deactivates after it.
+    pub duration: Option<f32>,
+    /// restart the emitter cycle after `duration`
+    pub looping: Option<bool>,
+    /// seconds before the emitter starts
+    pub start_delay: Option<f32>,
+    /// particles fired at fixed times of the emitter cycle
+    pub bursts: Option<Vec<Burst>>,
 }
 
 impl Default for Particle2dEffect {
@@ -237,23 +629,51 @@ impl Default for Particle2dEffect {
         Self {
             spawn_rate: 0.1,
             spawn_amount: 1,
+            spawn_distance: None,
+            spawn_per_second: None,
             emission_shape: EmissionShape::Point,
-            lifetime: Rval::new(1., 0.0),
-            linear_speed: Some(Rval(100., 0.1)),
+            emit_from_edge: None,
+            lifetime: Rdist::new(1., 0.0),
+            linear_speed: Some(Rdist::new(100., 0.1)),
             linear_acceleration: None,
             direction: Some(Rval(Vec2::Y, 0.1)),
+            direction_mode: None,
             angular_speed: None,
             angular_acceleration: None,
-            scale: Some(Rval(5., 1.)),
+            rotation: None,
+            rotation_mode: None,
+            scale: Some(Rdist::new(5., 1.)),
+            scale_y: None,
+            stretch: None,
             color: None,
+            random_color: None,
             gravity_direction: None,
             gravity_speed: None,
             linear_damp: None,
             angular_damp: None,
+            radial_acceleration: None,
+            tangential_acceleration: None,
             scale_curve: None,
+            scale_y_curve: None,
+            scale_curve_input: None,
             color_curve: None,
+            color_curve_mode: None,
+            color_curve_input: None,
+            speed_curve: None,
+            max_speed_curve: None,
+            angular_speed_curve: None,
+            damping_curve: None,
             attractors: None,
+            turbulence: None,
+            collision: None,
+            sub_emitters: None,
+            trail: None,
+            animation: None,
             relative_positioning: None,
+            duration: None,
+            looping: None,
+            start_delay: None,
+            bursts: None,
         }
     }
 }
@@ -1,5 +1,5 @@
 use super::ParticleEffectInstance;
-use crate::{Particle2dEffect, ParticleEffectHandle};
+use crate::{shape::EmissionShape, Particle2dEffect, ParticleEffectHandle};
 use bevy_asset::{io::Reader, AssetEvent, AssetLoadError, AssetLoader, Assets, LoadContext};
 use bevy_ecs::{
     component::Component,
@@ -21,7 +21,7 @@ impl AssetLoader for ParticleEffectLoader {
         &self,
         reader: &mut dyn Reader,
         _settings: &Self::Settings,
-        _load_context: &mut LoadContext<'_>,
+        load_context: &mut LoadContext<'_>,
     ) -> Result<Self::Asset, Self::Error> {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await.unwrap();
@@ -32,9 +32,65 @@ impl AssetLoader for ParticleEffectLoader {
             curve.sort();
         }
 
+        for curve in [
+            &mut asset.scale_y_curve,
+            &mut asset.speed_curve,
+            &mut asset.max_speed_curve,
+            &mut asset.angular_speed_curve,
+            &mut asset.damping_curve,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            curve.sort();
+        }
+
         if let Some(curve) = asset.color_curve.as_mut() {
             curve.sort();
         }
+        if let Some(color) = asset.random_color.as_mut() {
+            color.sort();
+        }
+
+        if let Some(trail) = asset.trail.as_mut() {
+            if let Some(curve) = trail.width_curve.as_mut() {
+                curve.sort();
+            }
+            if let Some(curve) = trail.color_curve.as_mut() {
+                curve.sort();
+            }
+        }
+
+        asset.lifetime.sort();
+        for value in [
+            &mut asset.linear_speed,
+            &mut asset.linear_acceleration,
+            &mut asset.angular_speed,
+            &mut asset.angular_acceleration,
+            &mut asset.rotation,
+            &mut asset.scale,
+            &mut asset.scale_y,
+            &mut asset.gravity_speed,
+            &mut asset.linear_damp,
+            &mut asset.angular_damp,
+            &mut asset.radial_acceleration,
+            &mut asset.tangential_acceleration,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            value.sort();
+        }
+
+        for sub_emitter in asset.sub_emitters.iter_mut().flatten() {
+            sub_emitter.handle = load_context.load(sub_emitter.effect.clone());
+        }
+
+        if let EmissionShape::Image(mask) = &mut asset.emission_shape {
+            if !mask.path.is_empty() {
+                mask.image = load_context.load(mask.path.clone());
+            }
+        }
 
         Ok(asset)
     }
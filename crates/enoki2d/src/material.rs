@@ -1,7 +1,11 @@
-use crate::RenderParticleTag;
+use crate::{
+    trail::{build_ribbon, TrailVertex},
+    update::ParticleEffectInstance,
+    RenderParticleTag,
+};
 
 use super::{ParticleSpawner, ParticleStore};
-use bevy_app::{App, Plugin};
+use bevy_app::{App, Plugin, Update};
 use bevy_asset::{Asset, AssetApp, AssetEvent, AssetId, AssetServer, Assets, Handle};
 use bevy_camera::visibility::ViewVisibility;
 use bevy_core_pipeline::core_2d::{Transparent2d, CORE_2D_DEPTH_FORMAT};
@@ -14,11 +18,11 @@ use bevy_ecs::{
     schedule::IntoScheduleConfigs,
     system::{
         lifetimeless::{Read, SRes},
-        Commands, Query, Res, ResMut, SystemParamItem,
+        Commands, Local, Query, Res, ResMut, SystemParamItem,
     },
     world::{FromWorld, World},
 };
-use bevy_math::{FloatOrd, Vec4};
+use bevy_math::{FloatOrd, UVec2, Vec2, Vec4};
 use bevy_mesh::{PrimitiveTopology, VertexBufferLayout};
 use bevy_reflect::Reflect;
 use bevy_render::{
@@ -46,6 +50,7 @@ use bevy_render::{
 use bevy_shader::{Shader, ShaderRef};
 use bevy_sprite_render::Mesh2dPipelineKey;
 use bevy_transform::components::GlobalTransform;
+use half::f16;
 use std::{hash::Hash, ops::Range};
 
 /// Particle Material Trait
@@ -71,6 +76,13 @@ impl<M: Particle2dMaterial> Default for Particle2dMaterialPlugin<M> {
 impl<M: Particle2dMaterial> Plugin for Particle2dMaterialPlugin<M> {
     fn build(&self, app: &mut App) {
         app.init_asset::<M>();
+        app.add_systems(
+            Update,
+            crate::sub_emitter::attach_sub_emitters::<M>
+                .after(crate::update::clone_effect)
+                .after(crate::loader::reload_effect)
+                .before(crate::update::update_spawner),
+        );
 
         app.add_plugins(RenderAssetPlugin::<PreparedParticleMaterial<M>>::default());
         app.sub_app_mut(RenderApp)
@@ -134,6 +146,8 @@ impl<M: Particle2dMaterial> Default for ExtractedParticleMaterials<M> {
 #[derive(Resource, Debug)]
 pub struct ExtracedParticleSpawner<M: Particle2dMaterial> {
     particles: EntityHashMap<Range<u32>>,
+    /// index ranges of spawners with trails
+    trails: EntityHashMap<Range<u32>>,
     _m: std::marker::PhantomData<M>,
 }
 
@@ -141,6 +155,7 @@ impl<M: Particle2dMaterial> Default for ExtracedParticleSpawner<M> {
     fn default() -> Self {
         Self {
             particles: Default::default(),
+            trails: Default::default(),
             _m: Default::default(),
         }
     }
@@ -179,21 +194,49 @@ fn extract_particles<M: Particle2dMaterial>(
             &ParticleStore,
             &GlobalTransform,
             &ParticleSpawner<M>,
+            &ParticleEffectInstance,
             &ViewVisibility,
             &RenderEntity,
         )>,
     >,
+    mut ribbon: Local<(Vec<TrailVertex>, Vec<u32>)>,
 ) {
     extraced_batches.particles.clear();
+    extraced_batches.trails.clear();
     particle_buffer.instance_buffer.clear();
+    particle_buffer.trail_vertex_buffer.clear();
+    particle_buffer.trail_index_buffer.clear();
     query.iter().for_each(|emitter| {
-        let (particle_store, global, material_handle, visbility, render_entity) = emitter;
+        let (particle_store, global, material_handle, effect, visbility, render_entity) = emitter;
         if !visbility.get() || particle_store.is_empty() {
             return;
         }
 
         cmd.entity(**render_entity)
             .insert((ZOrder(FloatOrd(global.translation().z)), ParticleTag));
+        render_material_instances.insert(**render_entity, material_handle.id());
+
+        if let Some(trail) = effect.as_ref().and_then(|effect| effect.trail.as_ref()) {
+            let (vertices, indices) = &mut *ribbon;
+            vertices.clear();
+            indices.clear();
+            for index in 0..particle_store.len() {
+                build_ribbon(particle_store, index, trail, vertices, indices);
+            }
+
+            let offset = particle_buffer.trail_vertex_buffer.len() as u32;
+            let start = particle_buffer.trail_index_buffer.len() as u32;
+            for vertex in vertices.drain(..) {
+                particle_buffer.trail_vertex_buffer.push(vertex);
+            }
+            for index in indices.drain(..) {
+                particle_buffer.trail_index_buffer.push(offset + index);
+            }
+            let end = particle_buffer.trail_index_buffer.len() as u32;
+            extraced_batches.trails.insert(**render_entity, start..end);
+            return;
+        }
+
         let start = particle_buffer.instance_buffer.len() as u32;
         for index in 0..particle_store.len() {
             particle_buffer
@@ -201,7 +244,6 @@ fn extract_particles<M: Particle2dMaterial>(
                 .push(InstanceData::from_store(particle_store, index));
         }
         let end = particle_buffer.instance_buffer.len() as u32;
-        render_material_instances.insert(**render_entity, material_handle.id());
         extraced_batches
             .particles
             .insert(**render_entity, start..end);
@@ -239,17 +281,33 @@ fn queue_particles<M: Particle2dMaterial>(
         let mesh_key = Mesh2dPipelineKey::from_msaa_samples(msaa.samples())
             | Mesh2dPipelineKey::from_target_format(view.target_format);
 
-        let key = Particle2dPipelineKey { mesh_key };
-        let pipeline = pipelines.specialize(&pipeline_cache, &custom_pipeline, key);
+        let key = Particle2dPipelineKey {
+            mesh_key,
+            trail: false,
+        };
+        let pipeline = pipelines.specialize(&pipeline_cache, &custom_pipeline, key.clone());
+        let trail_pipeline = pipelines.specialize(
+            &pipeline_cache,
+            &custom_pipeline,
+            Particle2dPipelineKey { trail: true, ..key },
+        );
 
         let Some(visible_entities) = visible_entities.get::<RenderParticleTag>() else {
             continue;
         };
 
         for (entity, main_entity) in visible_entities.iter_visible() {
-            if extract_particles.particles.get(entity).is_none() {
+            let pipeline = if extract_particles.particles.contains_key(entity) {
+                pipeline
+            } else if extract_particles
+                .trails
+                .get(entity)
+                .is_some_and(|range| !range.is_empty())
+            {
+                trail_pipeline
+            } else {
                 continue;
-            }
+            };
 
             let Ok(order) = z_orders.get(*entity) else {
                 return;
@@ -276,7 +334,13 @@ fn queue_particles<M: Particle2dMaterial>(
 pub struct InstanceData {
     transform: Vec4,
     scale_lifetime: Vec4,
-    color: Vec4,
+    /// rgba as four halfs, keeping the stride at 48 bytes
+    color: UVec2,
+    /// sprite sheet frame, all bits set to play the sheet over the lifetime
+    frame: u32,
+    /// two halfs of the travel direction, scaled by the velocity stretch.
+    /// Zero without velocity
+    stretch: u32,
 }
 
 impl InstanceData {
@@ -296,16 +360,27 @@ impl InstanceData {
                 store.duration_fraction[index],
                 store.duration[index],
             ),
-            color: Vec4::new(
-                store.color_r[index],
-                store.color_g[index],
-                store.color_b[index],
-                store.color_a[index],
+            color: UVec2::new(
+                pack_halfs(store.color_r[index], store.color_g[index]),
+                pack_halfs(store.color_b[index], store.color_a[index]),
             ),
+            frame: store.frame[index],
+            stretch: {
+                let stretch = Vec2::new(store.velocity_x[index], store.velocity_y[index])
+                    .normalize_or_zero()
+                    * store.stretch[index];
+                pack_halfs(stretch.x, stretch.y)
+            },
         }
     }
 }
 
+/// packs two floats as a `Float16x2` vertex attribute
+#[inline(always)]
+fn pack_halfs(x: f32, y: f32) -> u32 {
+    u32::from(f16::from_f32(x).to_bits()) | u32::from(f16::from_f32(y).to_bits()) << 16
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -328,12 +403,16 @@ mod tests {
             rotation: vec![0.5; PARTICLES],
             scale_x: vec![1.0; PARTICLES],
             scale_y: vec![1.0; PARTICLES],
+            stretch: vec![1.0; PARTICLES],
+            velocity_x: vec![1.0; PARTICLES],
+            velocity_y: vec![0.0; PARTICLES],
             duration: vec![10.0; PARTICLES],
             duration_fraction: vec![0.5; PARTICLES],
             color_r: vec![1.0; PARTICLES],
             color_g: vec![0.5; PARTICLES],
             color_b: vec![0.25; PARTICLES],
             color_a: vec![1.0; PARTICLES],
+            frame: vec![0; PARTICLES],
             ..Default::default()
         };
         let mut buffer = BufferVec::new(BufferUsages::VERTEX);
@@ -380,16 +459,27 @@ mod tests {
         store.scale_x.push(4.0);
         store.scale_y.push(5.0);
         store.scale_z.push(6.0);
+        store.stretch.push(2.0);
+        store.velocity_x.push(0.0);
+        store.velocity_y.push(-3.0);
         store.duration.push(10.0);
         store.duration_fraction.push(0.25);
         store.color_r.push(1.0);
         store.color_g.push(0.5);
         store.color_b.push(0.25);
         store.color_a.push(1.0);
+        store.frame.push(7);
 
         let instance = InstanceData::from_store(&store, 0);
         assert_eq!(instance.transform, Vec4::new(1.0, 2.0, 3.0, 0.5));
         assert_eq!(instance.scale_lifetime, Vec4::new(4.0, 5.0, 0.25, 10.0));
+        assert_eq!(instance.frame, 7);
+        let unpack = |halfs: u32| {
+            [halfs as u16, (halfs >> 16) as u16].map(|bits| f16::from_bits(bits).to_f32())
+        };
+        assert_eq!(unpack(instance.color.x), [1.0, 0.5]);
+        assert_eq!(unpack(instance.color.y), [0.25, 1.0]);
+        assert_eq!(unpack(instance.stretch), [0.0, -2.0]);
     }
 }
 
@@ -472,14 +562,33 @@ fn prepare_particles_instance_buffers<M: Particle2dMaterial>(
         if range.is_empty() {
             continue;
         }
-        cmd.entity(*entity).insert(ParticleInstanceBatch {
-            range: range.clone(),
-        });
+        cmd.entity(*entity)
+            .insert(ParticleInstanceBatch {
+                range: range.clone(),
+            })
+            .remove::<ParticleTrailBatch>();
+    }
+
+    for (entity, range) in extracted_spawner.trails.iter() {
+        if range.is_empty() {
+            continue;
+        }
+        cmd.entity(*entity)
+            .insert(ParticleTrailBatch {
+                range: range.clone(),
+            })
+            .remove::<ParticleInstanceBatch>();
     }
 
     particle_buffer
         .instance_buffer
         .write_buffer(&render_device, &render_queue);
+    particle_buffer
+        .trail_vertex_buffer
+        .write_buffer(&render_device, &render_queue);
+    particle_buffer
+        .trail_index_buffer
+        .write_buffer(&render_device, &render_queue);
 }
 
 #[derive(Resource)]
@@ -487,6 +596,8 @@ pub struct InstanceBuffer<M: Particle2dMaterial> {
     view_bind_group: Option<BindGroup>,
     instance_buffer: BufferVec<InstanceData>,
     index_buffer: BufferVec<u32>,
+    trail_vertex_buffer: BufferVec<TrailVertex>,
+    trail_index_buffer: BufferVec<u32>,
     _m: std::marker::PhantomData<M>,
 }
 
@@ -496,6 +607,8 @@ impl<M: Particle2dMaterial> Default for InstanceBuffer<M> {
             view_bind_group: None,
             instance_buffer: BufferVec::<InstanceData>::new(BufferUsages::VERTEX),
             index_buffer: BufferVec::<u32>::new(BufferUsages::INDEX),
+            trail_vertex_buffer: BufferVec::<TrailVertex>::new(BufferUsages::VERTEX),
+            trail_index_buffer: BufferVec::<u32>::new(BufferUsages::INDEX),
             _m: Default::default(),
         }
     }
@@ -505,12 +618,19 @@ impl<M: Particle2dMaterial> Default for InstanceBuffer<M> {
 pub struct ParticleInstanceBatch {
     pub range: Range<u32>,
 }
+
+/// index range of the ribbons of a spawner
+#[derive(Component, Debug)]
+pub struct ParticleTrailBatch {
+    pub range: Range<u32>,
+}
 // ----------------------------------------------
 // pipeline
 
 #[derive(Resource)]
 pub struct Particle2dPipeline<M: Particle2dMaterial> {
     vertex_shader: Handle<Shader>,
+    trail_vertex_shader: Handle<Shader>,
     fragment_shader: Handle<Shader>,
     uniform_layout: BindGroupLayoutDescriptor,
     view_layout: BindGroupLayoutDescriptor,
@@ -520,6 +640,8 @@ pub struct Particle2dPipeline<M: Particle2dMaterial> {
 #[derive(PartialEq, Eq, Hash, Clone)]
 pub struct Particle2dPipelineKey {
     mesh_key: Mesh2dPipelineKey,
+    /// ribbon vertices instead of instanced quads
+    trail: bool,
 }
 
 impl<M: Particle2dMaterial> FromWorld for Particle2dPipeline<M> {
@@ -547,6 +669,7 @@ impl<M: Particle2dMaterial> FromWorld for Particle2dPipeline<M> {
             view_layout,
             uniform_layout: M::bind_group_layout_descriptor(render_device), //world.resource::<ParticleUniformLayout>().0.clone(),
             vertex_shader,
+            trail_vertex_shader: super::PARTICLE_TRAIL_VERTEX,
             fragment_shader,
             _m: std::marker::PhantomData::<M>,
         }
@@ -558,14 +681,16 @@ impl<M: Particle2dMaterial> SpecializedRenderPipeline for Particle2dPipeline<M>
 
     fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
         let layout = vec![self.view_layout.clone(), self.uniform_layout.clone()];
-
-        RenderPipelineDescriptor {
-            zero_initialize_workgroup_memory: true,
-            vertex: bevy_render::render_resource::VertexState {
-                shader: self.vertex_shader.clone(),
-                shader_defs: vec![],
-                entry_point: Some("vertex".into()),
-                buffers: vec![VertexBufferLayout {
+        let (shader, buffer, shader_defs) = if key.trail {
+            (
+                self.trail_vertex_shader.clone(),
+                TrailVertex::vertex_layout(),
+                vec!["PARTICLE_TRAIL".into()],
+            )
+        } else {
+            (
+                self.vertex_shader.clone(),
+                VertexBufferLayout {
                     array_stride: 48,
                     step_mode: VertexStepMode::Instance,
                     attributes: vec![
@@ -581,18 +706,41 @@ impl<M: Particle2dMaterial> SpecializedRenderPipeline for Particle2dPipeline<M>
                             offset: 16,
                             shader_location: 1,
                         },
-                        // color
+                        // color, as halfs
                         VertexAttribute {
-                            format: VertexFormat::Float32x4,
+                            format: VertexFormat::Float16x4,
                             offset: 32,
                             shader_location: 2,
                         },
+                        // sprite frame
+                        VertexAttribute {
+                            format: VertexFormat::Uint32,
+                            offset: 40,
+                            shader_location: 3,
+                        },
+                        // velocity stretch, as halfs
+                        VertexAttribute {
+                            format: VertexFormat::Float16x2,
+                            offset: 44,
+                            shader_location: 4,
+                        },
                     ],
-                }],
+                },
+                vec![],
+            )
+        };
+
+        RenderPipelineDescriptor {
+            zero_initialize_workgroup_memory: true,
+            vertex: bevy_render::render_resource::VertexState {
+                shader,
+                shader_defs: shader_defs.clone(),
+                entry_point: Some("vertex".into()),
+                buffers: vec![buffer],
             },
             fragment: Some(bevy_render::render_resource::FragmentState {
                 shader: self.fragment_shader.clone(),
-                shader_defs: vec![],
+                shader_defs,
                 entry_point: Some("fragment".into()),
                 targets: vec![Some(ColorTargetState {
                     format: key.mesh_key.target_format(),
@@ -715,22 +863,43 @@ struct DrawParticleInstanced<M: Particle2dMaterial>(std::marker::PhantomData<M>)
 impl<P: PhaseItem, M: Particle2dMaterial> RenderCommand<P> for DrawParticleInstanced<M> {
     type Param = SRes<InstanceBuffer<M>>;
     type ViewQuery = ();
-    type ItemQuery = Read<ParticleInstanceBatch>;
+    type ItemQuery = (
+        Option<Read<ParticleInstanceBatch>>,
+        Option<Read<ParticleTrailBatch>>,
+    );
 
     #[inline]
     fn render<'w>(
         _item: &P,
         _view: (),
-        instance_buffer: Option<&'w ParticleInstanceBatch>,
+        batches: Option<(
+            Option<&'w ParticleInstanceBatch>,
+            Option<&'w ParticleTrailBatch>,
+        )>,
         meta: SystemParamItem<'w, '_, Self::Param>,
         pass: &mut TrackedRenderPass<'w>,
     ) -> RenderCommandResult {
-        let Some(batch) = instance_buffer else {
+        let particle_meta = meta.into_inner();
+
+        let (instance_batch, trail_batch) = batches.unwrap_or_default();
+        if let Some(batch) = trail_batch {
+            let Some(vertex_buffer) = particle_meta.trail_vertex_buffer.buffer() else {
+                return RenderCommandResult::Failure("Trail buffer was never written to GPU");
+            };
+            let Some(index_buffer) = particle_meta.trail_index_buffer.buffer() else {
+                return RenderCommandResult::Failure("Trail index buffer was never written to GPU");
+            };
+
+            pass.set_index_buffer(index_buffer.slice(..), IndexFormat::Uint32);
+            pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            pass.draw_indexed(batch.range.clone(), 0, 0..1);
+            return RenderCommandResult::Success;
+        }
+
+        let Some(batch) = instance_batch else {
             return RenderCommandResult::Failure("No batch buffer prepared");
         };
 
-        let particle_meta = meta.into_inner();
-
         let Some(instance_buffer) = particle_meta.instance_buffer.buffer() else {
             return RenderCommandResult::Failure("Instance buffer was never written to GPU");
         };
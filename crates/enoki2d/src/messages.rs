@@ -0,0 +1,157 @@
+use crate::update::{ParticleSpawnerState, ParticleStore};
+use bevy_color::LinearRgba;
+use bevy_ecs::{
+    change_detection::DetectChangesMut,
+    component::Component,
+    entity::Entity,
+    lifecycle::RemovedComponents,
+    message::{Message, MessageWriter},
+    query::{Changed, With},
+    reflect::ReflectComponent,
+    system::Query,
+};
+use bevy_math::Vec2;
+use bevy_reflect::{prelude::ReflectDefault, Reflect};
+
+/// Opts a spawner into particle messages.
+/// The flags choose, which of them are sent.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component, Debug, Default)]
+pub struct ParticleMessages {
+    /// `ParticlesSpawned`, once per frame with new particles
+    pub spawned: bool,
+    /// `ParticleDied`, for every particle at the end of its lifetime
+    pub died: bool,
+    /// `ParticleCollided`, for every collision
+    pub collided: bool,
+}
+
+impl ParticleMessages {
+    pub const ALL: Self = Self {
+        spawned: true,
+        died: true,
+        collided: true,
+    };
+}
+
+/// A particle at the moment of the message, in world space.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct ParticleSnapshot {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub color: LinearRgba,
+}
+
+impl ParticleSnapshot {
+    pub(crate) fn new(particles: &ParticleStore, index: usize) -> Self {
+        Self {
+            position: Vec2::new(particles.position_x[index], particles.position_y[index]),
+            velocity: Vec2::new(particles.velocity_x[index], particles.velocity_y[index]),
+            color: LinearRgba::new(
+                particles.color_r[index],
+                particles.color_g[index],
+                particles.color_b[index],
+                particles.color_a[index],
+            ),
+        }
+    }
+}
+
+/// particles a spawner created during one frame
+#[derive(Message, Clone, Debug, PartialEq)]
+pub struct ParticlesSpawned {
+    pub spawner: Entity,
+    pub particles: Vec<ParticleSnapshot>,
+}
+
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct ParticleDied {
+    pub spawner: Entity,
+    pub particle: ParticleSnapshot,
+}
+
+/// a particle hit a collider, `particle` is the state after the collision response
+#[derive(Message, Clone, Copy, Debug, PartialEq)]
+pub struct ParticleCollided {
+    pub spawner: Entity,
+    pub particle: ParticleSnapshot,
+    pub normal: Vec2,
+}
+
+/// messages of a single spawner, collected during the parallel update
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MessageBuffer {
+    pub(crate) enabled: ParticleMessages,
+    pub(crate) spawned: Vec<ParticleSnapshot>,
+    pub(crate) died: Vec<ParticleSnapshot>,
+    pub(crate) collided: Vec<(ParticleSnapshot, Vec2)>,
+}
+
+impl MessageBuffer {
+    pub(crate) fn spawned(&mut self, particles: &ParticleStore, index: usize) {
+        if self.enabled.spawned {
+            self.spawned.push(ParticleSnapshot::new(particles, index));
+        }
+    }
+
+    pub(crate) fn died(&mut self, particles: &ParticleStore, index: usize) {
+        if self.enabled.died {
+            self.died.push(ParticleSnapshot::new(particles, index));
+        }
+    }
+
+    pub(crate) fn collided(&mut self, particles: &ParticleStore, index: usize, normal: Vec2) {
+        if self.enabled.collided {
+            self.collided
+                .push((ParticleSnapshot::new(particles, index), normal));
+        }
+    }
+}
+
+pub(crate) fn apply_message_flags(
+    changed: Query<(Entity, &ParticleMessages), Changed<ParticleMessages>>,
+    mut removed: RemovedComponents<ParticleMessages>,
+    mut states: Query<&mut ParticleSpawnerState>,
+) {
+    for (entity, messages) in changed.iter() {
+        if let Ok(mut state) = states.get_mut(entity) {
+            state.messages.enabled = *messages;
+        }
+    }
+
+    for entity in removed.read() {
+        if let Ok(mut state) = states.get_mut(entity) {
+            state.messages = MessageBuffer::default();
+        }
+    }
+}
+
+pub(crate) fn send_particle_messages(
+    mut particle_spawners: Query<(Entity, &mut ParticleSpawnerState), With<ParticleMessages>>,
+    mut spawned: MessageWriter<ParticlesSpawned>,
+    mut died: MessageWriter<ParticleDied>,
+    mut collided: MessageWriter<ParticleCollided>,
+) {
+    for (spawner, mut state) in particle_spawners.iter_mut() {
+        let buffer = &mut state.bypass_change_detection().messages;
+        if !buffer.spawned.is_empty() {
+            spawned.write(ParticlesSpawned {
+                spawner,
+                particles: std::mem::take(&mut buffer.spawned),
+            });
+        }
+        died.write_batch(
+            buffer
+                .died
+                .drain(..)
+                .map(|particle| ParticleDied { spawner, particle }),
+        );
+        collided.write_batch(buffer.collided.drain(..).map(|(particle, normal)| {
+            ParticleCollided {
+                spawner,
+                particle,
+                normal,
+            }
+        }));
+    }
+}
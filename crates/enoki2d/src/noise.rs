@@ -0,0 +1,202 @@
+use bevy_math::Vec2;
+use bevy_reflect::Reflect;
+use serde::{Deserialize, Serialize};
+use wide::{f32x8, i32x8};
+
+/// Organic wobble, pushes particles along a scrolling noise field.
+/// The field only depends on the seed, so effects stay reproducible.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Reflect)]
+pub struct Turbulence {
+    /// acceleration in world units per second²
+    pub strength: f32,
+    /// noise cells per world unit
+    pub frequency: f32,
+    /// layers of finer noise, defaults to 1
+    pub octaves: Option<u32>,
+    /// world units per second the field moves
+    pub scroll: Option<Vec2>,
+    pub seed: Option<u32>,
+    /// defaults to `Curl`
+    pub mode: Option<NoiseMode>,
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum NoiseMode {
+    /// swirling, divergence free flow. Particles don't clump.
+    #[default]
+    Curl,
+    /// two independent noise values as force, more chaotic
+    Value,
+}
+
+impl Turbulence {
+    pub fn new(strength: f32, frequency: f32) -> Self {
+        Self {
+            strength,
+            frequency,
+            octaves: None,
+            scroll: None,
+            seed: None,
+            mode: None,
+        }
+    }
+
+    /// acceleration at 8 world positions, `time` in seconds scrolls the field
+    pub fn force8(&self, x: f32x8, y: f32x8, time: f32) -> (f32x8, f32x8) {
+        let offset = self.scroll.unwrap_or_default() * time;
+        let frequency = f32x8::splat(self.frequency);
+        let x = (x - f32x8::splat(offset.x)) * frequency;
+        let y = (y - f32x8::splat(offset.y)) * frequency;
+        let seed = self.seed.unwrap_or(0) as i32;
+        let strength = f32x8::splat(self.strength);
+
+        match self.mode.unwrap_or_default() {
+            NoiseMode::Curl => {
+                let (_, dx, dy) = self.fractal8(x, y, seed);
+                (dy * strength, -dx * strength)
+            }
+            NoiseMode::Value => {
+                let (fx, _, _) = self.fractal8(x, y, seed);
+                let (fy, _, _) = self.fractal8(x, y, seed.wrapping_add(0x5bd1));
+                (fx * strength, fy * strength)
+            }
+        }
+    }
+
+    /// acceleration at a world position, same as one lane of `force8`
+    pub fn force(&self, position: Vec2, time: f32) -> Vec2 {
+        let (x, y) = self.force8(f32x8::splat(position.x), f32x8::splat(position.y), time);
+        Vec2::new(x.to_array()[0], y.to_array()[0])
+    }
+
+    /// sum of octaves, with halved amplitude and doubled frequency
+    fn fractal8(&self, x: f32x8, y: f32x8, seed: i32) -> (f32x8, f32x8, f32x8) {
+        let (mut value, mut dx, mut dy) = (f32x8::ZERO, f32x8::ZERO, f32x8::ZERO);
+        let mut amplitude = 1.;
+        let mut frequency = 1.;
+        for octave in 0..self.octaves.unwrap_or(1).max(1) {
+            let scale = f32x8::splat(frequency);
+            let (v, vx, vy) = value_noise8(x * scale, y * scale, seed.wrapping_add(octave as i32));
+            let amplitude8 = f32x8::splat(amplitude);
+            value += v * amplitude8;
+            dx += vx * amplitude8 * scale;
+            dy += vy * amplitude8 * scale;
+            amplitude *= 0.5;
+            frequency *= 2.;
+        }
+        (value, dx, dy)
+    }
+}
+
+/// value noise between -1 and 1, with its partial derivatives
+fn value_noise8(x: f32x8, y: f32x8, seed: i32) -> (f32x8, f32x8, f32x8) {
+    let (floor_x, floor_y) = (x.floor(), y.floor());
+    let (ix, iy) = (floor_x.round_int(), floor_y.round_int());
+    let (fx, fy) = (x - floor_x, y - floor_y);
+
+    let a = lattice8(ix, iy, seed);
+    let b = lattice8(ix + 1, iy, seed);
+    let c = lattice8(ix, iy + 1, seed);
+    let d = lattice8(ix + 1, iy + 1, seed);
+
+    let (u, du) = fade8(fx);
+    let (v, dv) = fade8(fy);
+    let k = a - b - c + d;
+
+    let value = a + (b - a) * u + (c - a) * v + k * u * v;
+    let dx = du * ((b - a) + k * v);
+    let dy = dv * ((c - a) + k * u);
+    (value, dx, dy)
+}
+
+/// quintic smoothstep and its derivative
+fn fade8(t: f32x8) -> (f32x8, f32x8) {
+    let t2 = t * t;
+    let value = t2 * t * (t * (t * f32x8::splat(6.) - f32x8::splat(15.)) + f32x8::splat(10.));
+    let inverse = t - f32x8::ONE;
+    let derivative = f32x8::splat(30.) * t2 * inverse * inverse;
+    (value, derivative)
+}
+
+/// random value between -1 and 1 for a lattice point
+fn lattice8(x: i32x8, y: i32x8, seed: i32) -> f32x8 {
+    let mut hash = x * 0x27d4_eb2d + y * 0x1656_67b1 + i32x8::splat(seed) * 0x3c6e_f372;
+    hash = (hash ^ (hash >> 15)) * 0x2c1b_3c6d;
+    hash = (hash ^ (hash >> 12)) * 0x297a_2d39;
+    hash = hash ^ (hash >> 15);
+    let unit = f32x8::from_i32x8(hash & i32x8::splat(0xffff)) / f32x8::splat(65535.);
+    unit * f32x8::splat(2.) - f32x8::ONE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_is_seeded_and_continuous() {
+        let turbulence = Turbulence {
+            octaves: Some(3),
+            seed: Some(7),
+            ..Turbulence::new(10., 0.05)
+        };
+
+        let position = Vec2::new(12.5, -40.25);
+        let force = turbulence.force(position, 0.);
+        assert_eq!(force, turbulence.force(position, 0.));
+        assert_ne!(
+            force,
+            Turbulence {
+                seed: Some(8),
+                ..turbulence.clone()
+            }
+            .force(position, 0.)
+        );
+
+        // small steps, small changes
+        let near = turbulence.force(position + Vec2::splat(0.01), 0.);
+        assert!(force.distance(near) < 0.1, "{force} {near}");
+
+        // lanes are independent
+        let xs = f32x8::new([0., 10., 20., 30., 40., 50., 60., 70.]);
+        let (fx, fy) = turbulence.force8(xs, f32x8::splat(5.), 0.);
+        for (lane, x) in xs.to_array().iter().enumerate() {
+            let scalar = turbulence.force(Vec2::new(*x, 5.), 0.);
+            assert_eq!(scalar, Vec2::new(fx.to_array()[lane], fy.to_array()[lane]));
+        }
+
+        // scrolling moves the field
+        let scrolled = Turbulence {
+            scroll: Some(Vec2::new(20., 0.)),
+            ..turbulence.clone()
+        };
+        assert_eq!(
+            scrolled.force(position + Vec2::new(20., 0.), 1.),
+            turbulence.force(position, 0.)
+        );
+    }
+
+    #[test]
+    fn noise_output_is_locked() {
+        let turbulence = Turbulence {
+            octaves: Some(2),
+            seed: Some(1),
+            ..Turbulence::new(1., 0.1)
+        };
+        let value = Turbulence {
+            mode: Some(NoiseMode::Value),
+            ..turbulence.clone()
+        };
+
+        let position = Vec2::new(3.3, 7.7);
+        let curl = turbulence.force(position, 0.);
+        assert!(
+            curl.abs_diff_eq(Vec2::new(-0.935_126_6, 2.154_349_6), 1e-5),
+            "{curl}"
+        );
+        let force = value.force(position, 0.);
+        assert!(
+            force.abs_diff_eq(Vec2::new(0.338_728_7, -0.332_977_1), 1e-5),
+            "{force}"
+        );
+    }
+}
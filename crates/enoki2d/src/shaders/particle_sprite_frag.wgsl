@@ -3,17 +3,39 @@
 @group(1) @binding(0) var texture: texture_2d<f32>;
 @group(1) @binding(1) var texture_sampler: sampler;
 @group(1) @binding(2) var<uniform> frame_data: vec4<u32>;
+@group(1) @binding(3) var<uniform> atlas: Atlas;
+
+struct Atlas {
+    count: u32,
+    // min xy, max zw
+    rects: array<vec4<f32>, 64>,
+};
 
 
 @fragment
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
 	var out = in.color;
 
+	// packed atlas regions instead of the grid
+	if atlas.count > 0u {
+		var atlas_frame = min(u32(floor(in.lifetime_frac * f32(atlas.count))), atlas.count - 1u);
+		if in.frame != 0xffffffffu {
+			atlas_frame = in.frame % atlas.count;
+		}
+		let rect = atlas.rects[atlas_frame];
+		let atlas_uv = mix(rect.xy, rect.zw, in.uv);
+		return out * textureSample(texture, texture_sampler, atlas_uv);
+	}
+
 	let max_hframe = f32(frame_data.x);
 	let max_vframe = f32(frame_data.y);
 
     let total_frames = max_hframe * max_vframe;
-    let current_frame = floor(in.lifetime_frac * total_frames);
+    // without a sprite animation the sheet plays over the lifetime
+    var current_frame = floor(in.lifetime_frac * total_frames);
+    if in.frame != 0xffffffffu {
+        current_frame = f32(in.frame) % total_frames;
+    }
 
     let hframe = current_frame % max_hframe;
     let vframe = floor(current_frame / max_hframe);
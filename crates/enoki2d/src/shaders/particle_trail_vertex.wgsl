@@ -0,0 +1,27 @@
+#import bevy_render::view::View
+#import bevy_enoki::particle_vertex_out::{ VertexOutput }
+
+@group(0) @binding(0) var<uniform> view: View;
+
+struct VertexIn {
+    @location(0) position: vec4<f32>,
+    @location(1) uv_lifetime: vec4<f32>,
+    @location(2) color: vec4<f32>,
+    @location(3) frame: u32,
+};
+
+@vertex
+fn vertex(in: VertexIn) -> VertexOutput {
+    var out: VertexOutput;
+
+    out.clip_position = view.clip_from_world * vec4(in.position.xyz, 1.0);
+
+    out.color = in.color;
+	out.uv = in.uv_lifetime.xy;
+
+	out.lifetime_frac = in.uv_lifetime.z;
+	out.lifetime_total = in.uv_lifetime.w;
+	out.frame = in.frame;
+
+    return out;
+}
@@ -8,6 +8,8 @@ struct VertexIn {
     @location(0) i_transform: vec4<f32>,
     @location(1) i_scale_lifetime: vec4<f32>,
     @location(2) i_color: vec4<f32>,
+    @location(3) i_frame: u32,
+    @location(4) i_stretch: vec2<f32>,
 };
 
 @vertex
@@ -25,10 +27,17 @@ fn vertex(in: VertexIn) -> VertexOutput {
     let angle = in.i_transform.w;
     let sine = sin(angle);
     let cosine = cos(angle);
-    let rotated = vec2(
+    var rotated = vec2(
         cosine * scaled.x - sine * scaled.y,
         sine * scaled.x + cosine * scaled.y,
     );
+
+    // lengthen the rotated quad along the travel direction
+    let stretch = length(in.i_stretch);
+    if stretch > 0.0 {
+        let axis = in.i_stretch / stretch;
+        rotated += axis * dot(rotated, axis) * (stretch - 1.0);
+    }
     let world_position = vec3(in.i_transform.xy + rotated, in.i_transform.z);
 
     out.clip_position = view.clip_from_world * vec4(world_position, 1.0);
@@ -38,6 +47,7 @@ fn vertex(in: VertexIn) -> VertexOutput {
 
 	out.lifetime_frac = in.i_scale_lifetime.z;
 	out.lifetime_total = in.i_scale_lifetime.w;
+	out.frame = in.i_frame;
 
     return out;
 }
@@ -2,8 +2,14 @@
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
+#ifdef PARTICLE_TRAIL
+    // ribbons blend the color along the trail
+    @location(0) color: vec4<f32>,
+#else
     @location(0) @interpolate(flat) color: vec4<f32>,
+#endif
 	@location(1) uv : vec2<f32>,
 	@location(2) lifetime_frac : f32,
 	@location(3) lifetime_total : f32,
+	@location(4) @interpolate(flat) frame : u32,
 };
@@ -0,0 +1,743 @@
+use crate::rng::ParticleRng;
+use bevy_asset::{AssetId, Handle};
+use bevy_color::LinearRgba;
+use bevy_ecs::resource::Resource;
+use bevy_image::Image;
+use bevy_math::{
+    primitives::{Annulus, Circle, CircularSector, Rectangle, Triangle2d},
+    ShapeSample, Vec2,
+};
+use bevy_reflect::Reflect;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+
+/// The area particles spawn in, relative to the spawner.
+/// Every shape can either be filled uniformly or only
+/// sampled along its edge, see `Particle2dEffect::emit_from_edge`.
+#[derive(Deserialize, Reflect, Default, Clone, Debug, Serialize, PartialEq)]
+#[reflect]
+pub enum EmissionShape {
+    #[default]
+    Point,
+    /// circle with a radius
+    Circle(f32),
+    /// axis aligned rectangle, rotated with the spawner
+    Rectangle { half_size: Vec2 },
+    /// ring between an inner and outer radius
+    Annulus {
+        inner_radius: f32,
+        outer_radius: f32,
+    },
+    /// circular arc around the local up axis. The area of an
+    /// arc is its circular sector. Negative half angles count as zero.
+    Arc { radius: f32, half_angle: f32 },
+    /// line between two points
+    Segment { start: Vec2, end: Vec2 },
+    /// closed polygon, convex or concave
+    Polygon(EmissionPolygon),
+    /// opaque pixels of an image
+    Image(ImageMask),
+}
+
+impl EmissionShape {
+    /// samples a random local position, either inside the shape
+    /// or on its edge. Image masks can also provide the color
+    /// of the sampled pixel.
+    pub fn sample(&self, rng: &mut ParticleRng, edge: bool) -> (Vec2, Option<LinearRgba>) {
+        let position = match self {
+            EmissionShape::Point => Vec2::ZERO,
+            EmissionShape::Circle(radius) => sample_shape(&Circle::new(*radius), rng, edge),
+            EmissionShape::Rectangle { half_size } => {
+                let rectangle = Rectangle {
+                    half_size: half_size.abs(),
+                };
+                sample_shape(&rectangle, rng, edge)
+            }
+            EmissionShape::Annulus {
+                inner_radius,
+                outer_radius,
+            } => {
+                let (inner, outer) = (
+                    inner_radius.min(*outer_radius),
+                    inner_radius.max(*outer_radius),
+                );
+                if inner == outer {
+                    // no area between the circles
+                    Circle::new(outer).sample_boundary(rng)
+                } else {
+                    sample_shape(&Annulus::new(inner, outer), rng, edge)
+                }
+            }
+            EmissionShape::Arc { radius, half_angle } => {
+                let half_angle = half_angle.max(0.);
+                if edge {
+                    // only the curved part, the straight sides of the sector are no arc.
+                    // bevy_math has no sampler for `Arc2d`
+                    let angle = rng.random_range(-half_angle..=half_angle);
+                    Vec2::from_angle(std::f32::consts::FRAC_PI_2 + angle) * *radius
+                } else {
+                    CircularSector::new(*radius, half_angle).sample_interior(rng)
+                }
+            }
+            // bevy_math has no sampler for `Segment2d`
+            EmissionShape::Segment { start, end } => start.lerp(*end, rng.random::<f32>()),
+            EmissionShape::Polygon(polygon) => {
+                if edge {
+                    polygon.sample_boundary(rng)
+                } else {
+                    polygon.sample_interior(rng)
+                }
+            }
+            EmissionShape::Image(mask) => return mask.sample(rng, edge),
+        };
+
+        (position, None)
+    }
+
+    /// outward normal of the edge closest to a local position.
+    /// Returns zero, if the shape has no edge to point away from.
+    pub fn normal_at(&self, position: Vec2) -> Vec2 {
+        match self {
+            EmissionShape::Point => Vec2::ZERO,
+            EmissionShape::Circle(_) | EmissionShape::Arc { .. } | EmissionShape::Image(_) => {
+                position.normalize_or_zero()
+            }
+            EmissionShape::Rectangle { half_size } => {
+                let margin = *half_size - position.abs();
+                if margin.x < margin.y {
+                    Vec2::X * position.x.signum()
+                } else {
+                    Vec2::Y * position.y.signum()
+                }
+            }
+            EmissionShape::Annulus {
+                inner_radius,
+                outer_radius,
+            } => {
+                let distance = position.length();
+                let radial = position.normalize_or_zero();
+                if distance - inner_radius < outer_radius - distance {
+                    -radial
+                } else {
+                    radial
+                }
+            }
+            EmissionShape::Segment { start, end } => (*end - *start).perp().normalize_or_zero(),
+            EmissionShape::Polygon(polygon) => polygon.normal_at(position),
+        }
+    }
+}
+
+/// How the initial direction of a particle is picked. Every
+/// mode, besides `Fixed`, derives it from where the particle spawned
+/// on the emission shape. The randomness of `Particle2dEffect::direction`
+/// is applied on top as spread.
+#[derive(Deserialize, Serialize, Reflect, Default, Clone, Copy, Debug, PartialEq)]
+#[reflect]
+pub enum DirectionMode {
+    /// uses `Particle2dEffect::direction`
+    #[default]
+    Fixed,
+    /// away from the shape center
+    Outward,
+    /// towards the shape center
+    Inward,
+    /// orbiting the shape center clockwise
+    TangentClockwise,
+    /// orbiting the shape center counter clockwise
+    TangentCounterClockwise,
+    /// along the outward normal of the closest edge
+    EdgeNormal,
+}
+
+impl DirectionMode {
+    /// the local direction for a particle spawned at `position`,
+    /// falls back to `fallback` at the shape center.
+    pub fn direction(&self, shape: &EmissionShape, position: Vec2, fallback: Vec2) -> Vec2 {
+        let direction = match self {
+            DirectionMode::Fixed => return fallback,
+            DirectionMode::Outward => position.normalize_or_zero(),
+            DirectionMode::Inward => -position.normalize_or_zero(),
+            DirectionMode::TangentClockwise => -position.normalize_or_zero().perp(),
+            DirectionMode::TangentCounterClockwise => position.normalize_or_zero().perp(),
+            DirectionMode::EdgeNormal => shape.normal_at(position),
+        };
+
+        if direction == Vec2::ZERO {
+            fallback
+        } else {
+            direction
+        }
+    }
+}
+
+/// A closed polygon for emission. Serialized as its list
+/// of vertices, the triangulation is computed on creation.
+#[derive(Deserialize, Serialize, Reflect, Default, Clone, Debug, PartialEq)]
+#[serde(from = "Vec<Vec2>", into = "Vec<Vec2>")]
+pub struct EmissionPolygon {
+    vertices: Vec<Vec2>,
+    #[reflect(ignore)]
+    triangles: Vec<Triangle2d>,
+    /// running total of the triangle areas
+    #[reflect(ignore)]
+    areas: Vec<f32>,
+    /// running total of the edge lengths
+    #[reflect(ignore)]
+    lengths: Vec<f32>,
+}
+
+impl EmissionPolygon {
+    pub fn new(vertices: impl IntoIterator<Item = Vec2>) -> Self {
+        let vertices: Vec<Vec2> = vertices.into_iter().collect();
+        let triangles = triangulate(&vertices);
+
+        let areas = triangles
+            .iter()
+            .scan(0., |total, triangle| {
+                let [a, b, c] = triangle.vertices;
+                *total += (b - a).perp_dot(c - a).abs() * 0.5;
+                Some(*total)
+            })
+            .collect();
+
+        let lengths = (0..vertices.len())
+            .scan(0., |total, index| {
+                let next = vertices[(index + 1) % vertices.len()];
+                *total += vertices[index].distance(next);
+                Some(*total)
+            })
+            .collect();
+
+        Self {
+            vertices,
+            triangles,
+            areas,
+            lengths,
+        }
+    }
+
+    pub fn vertices(&self) -> &[Vec2] {
+        &self.vertices
+    }
+
+    fn normal_at(&self, position: Vec2) -> Vec2 {
+        let len = self.vertices.len();
+        let (mut winding, mut closest, mut normal) = (0., f32::MAX, Vec2::ZERO);
+        for index in 0..len {
+            let start = self.vertices[index];
+            let end = self.vertices[(index + 1) % len];
+            winding += start.perp_dot(end);
+
+            let edge = end - start;
+            let t = ((position - start).dot(edge) / edge.length_squared()).clamp(0., 1.);
+            let distance = position.distance_squared(start + edge * t);
+            if distance < closest {
+                closest = distance;
+                normal = edge.perp().normalize_or_zero();
+            }
+        }
+        // `perp` points inside for counter clockwise polygons
+        if winding > 0. {
+            -normal
+        } else {
+            normal
+        }
+    }
+
+    fn sample_interior(&self, rng: &mut ParticleRng) -> Vec2 {
+        let Some(total) = self.areas.last() else {
+            return self.vertices.first().copied().unwrap_or_default();
+        };
+        let target = rng.random::<f32>() * total;
+        let index = self
+            .areas
+            .partition_point(|area| *area < target)
+            .min(self.triangles.len() - 1);
+        self.triangles[index].sample_interior(rng)
+    }
+
+    fn sample_boundary(&self, rng: &mut ParticleRng) -> Vec2 {
+        let Some(total) = self.lengths.last() else {
+            return Vec2::ZERO;
+        };
+        let target = rng.random::<f32>() * total;
+        let index = self
+            .lengths
+            .partition_point(|length| *length < target)
+            .min(self.vertices.len() - 1);
+        let start = self.vertices[index];
+        let end = self.vertices[(index + 1) % self.vertices.len()];
+        start.lerp(end, rng.random::<f32>())
+    }
+}
+
+impl From<Vec<Vec2>> for EmissionPolygon {
+    fn from(value: Vec<Vec2>) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<EmissionPolygon> for Vec<Vec2> {
+    fn from(value: EmissionPolygon) -> Self {
+        value.vertices
+    }
+}
+
+/// Emits from the pixels of an image, that are more opaque than
+/// `alpha_threshold`. The image is loaded as dependency of the effect
+/// and centered on the spawner.
+#[derive(Deserialize, Serialize, Reflect, Default, Clone, Debug, PartialEq)]
+pub struct ImageMask {
+    /// asset path of the image
+    pub path: String,
+    #[serde(skip)]
+    pub image: Handle<Image>,
+    /// minimum alpha of a pixel to emit from, defaults to 0.5
+    pub alpha_threshold: Option<f32>,
+    /// world size of one pixel, defaults to 1.0
+    pub scale: Option<f32>,
+    /// particles take the color of the pixel they spawned on
+    pub use_color: Option<bool>,
+    #[serde(skip)]
+    #[reflect(ignore)]
+    pixels: Arc<[MaskPixel]>,
+    #[serde(skip)]
+    #[reflect(ignore)]
+    edge_pixels: Arc<[MaskPixel]>,
+    /// the image the pixels were read from
+    #[serde(skip)]
+    #[reflect(ignore)]
+    prepared: Option<AssetId<Image>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct MaskPixel {
+    position: Vec2,
+    color: LinearRgba,
+}
+
+/// the pixels of an image, read for one threshold and scale
+#[derive(Clone)]
+struct PreparedMask {
+    pixels: Arc<[MaskPixel]>,
+    edge_pixels: Arc<[MaskPixel]>,
+}
+
+impl PreparedMask {
+    fn read(image: &Image, threshold: f32, scale: f32) -> Self {
+        let (width, height) = (image.width() as i64, image.height() as i64);
+        let center = Vec2::new(width as f32, height as f32) * 0.5;
+
+        // every pixel is decoded once, the edge test looks up the neighbours here
+        let opaque: Vec<Option<LinearRgba>> = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                image
+                    .get_color_at(x as u32, y as u32)
+                    .ok()
+                    .map(|color| color.to_linear())
+                    .filter(|color| color.alpha > threshold)
+            })
+            .collect();
+        let is_opaque = |x: i64, y: i64| {
+            x >= 0
+                && y >= 0
+                && x < width
+                && y < height
+                && opaque[(y * width + x) as usize].is_some()
+        };
+
+        let mut pixels = Vec::new();
+        let mut edge_pixels = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let Some(color) = opaque[(y * width + x) as usize] else {
+                    continue;
+                };
+
+                // image rows go down, world y goes up
+                let pixel = MaskPixel {
+                    position: (Vec2::new(x as f32 + 0.5, -(y as f32) - 0.5)
+                        + Vec2::new(-center.x, center.y))
+                        * scale,
+                    color,
+                };
+
+                if [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                    .iter()
+                    .any(|(dx, dy)| !is_opaque(x + dx, y + dy))
+                {
+                    edge_pixels.push(pixel);
+                }
+                pixels.push(pixel);
+            }
+        }
+
+        Self {
+            pixels: pixels.into(),
+            edge_pixels: edge_pixels.into(),
+        }
+    }
+}
+
+/// Prepared image masks, shared by every spawner with the same image,
+/// threshold and scale. Entries are dropped, when their image changes.
+#[derive(Resource, Default)]
+pub(crate) struct ImageMaskCache(HashMap<(AssetId<Image>, u32, u32), PreparedMask>);
+
+impl ImageMaskCache {
+    pub(crate) fn remove_image(&mut self, image: AssetId<Image>) {
+        self.0.retain(|(id, ..), _| *id != image);
+    }
+}
+
+impl ImageMask {
+    pub fn new(image: Handle<Image>) -> Self {
+        Self {
+            path: image
+                .path()
+                .map(|path| path.to_string())
+                .unwrap_or_default(),
+            image,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_alpha_threshold(mut self, alpha_threshold: f32) -> Self {
+        self.alpha_threshold = Some(alpha_threshold);
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    pub fn with_color(mut self) -> Self {
+        self.use_color = Some(true);
+        self
+    }
+
+    /// true, once the pixels are read from the current image.
+    /// A mask without any opaque pixel is ready too.
+    pub fn is_ready(&self) -> bool {
+        self.prepared == Some(self.image.id())
+    }
+
+    /// collects all pixels above the threshold.
+    pub fn prepare(&mut self, image: &Image) {
+        let prepared = PreparedMask::read(image, self.threshold(), self.scale());
+        self.apply(prepared);
+    }
+
+    /// like `prepare`, but masks with the same image, threshold and scale share their pixels
+    pub(crate) fn prepare_cached(&mut self, image: &Image, cache: &mut ImageMaskCache) {
+        let key = (
+            self.image.id(),
+            self.threshold().to_bits(),
+            self.scale().to_bits(),
+        );
+        let prepared = cache
+            .0
+            .entry(key)
+            .or_insert_with(|| PreparedMask::read(image, self.threshold(), self.scale()))
+            .clone();
+        self.apply(prepared);
+    }
+
+    fn threshold(&self) -> f32 {
+        self.alpha_threshold.unwrap_or(0.5)
+    }
+
+    fn scale(&self) -> f32 {
+        self.scale.unwrap_or(1.)
+    }
+
+    fn apply(&mut self, prepared: PreparedMask) {
+        self.pixels = prepared.pixels;
+        self.edge_pixels = prepared.edge_pixels;
+        self.prepared = Some(self.image.id());
+    }
+
+    fn sample(&self, rng: &mut ParticleRng, edge: bool) -> (Vec2, Option<LinearRgba>) {
+        let pixels = if edge {
+            &self.edge_pixels
+        } else {
+            &self.pixels
+        };
+        if pixels.is_empty() {
+            return (Vec2::ZERO, None);
+        }
+
+        let pixel = pixels[rng.random_range(0..pixels.len())];
+        let scale = self.scale();
+        let jitter = Vec2::new(rng.random::<f32>() - 0.5, rng.random::<f32>() - 0.5) * scale;
+        let color = self.use_color.unwrap_or(false).then_some(pixel.color);
+
+        (pixel.position + jitter, color)
+    }
+}
+
+/// ear clipping triangulation, works with concave polygons
+/// in either winding order.
+fn triangulate(vertices: &[Vec2]) -> Vec<Triangle2d> {
+    if vertices.len() < 3 {
+        return Vec::new();
+    }
+
+    let mut indices: Vec<usize> = (0..vertices.len()).collect();
+    let signed_area: f32 = (0..vertices.len())
+        .map(|index| vertices[index].perp_dot(vertices[(index + 1) % vertices.len()]))
+        .sum();
+    if signed_area < 0. {
+        indices.reverse();
+    }
+
+    let mut triangles = Vec::with_capacity(vertices.len() - 2);
+    while indices.len() > 3 {
+        let len = indices.len();
+        let corner = |index: usize| {
+            (
+                vertices[indices[(index + len - 1) % len]],
+                vertices[indices[index]],
+                vertices[indices[(index + 1) % len]],
+            )
+        };
+
+        let ear = (0..len).find(|index| {
+            let (a, b, c) = corner(*index);
+            if (b - a).perp_dot(c - b) <= 0. {
+                return false;
+            }
+            !indices.iter().any(|other| {
+                let p = vertices[*other];
+                p != a && p != b && p != c && in_triangle(p, a, b, c)
+            })
+        });
+
+        match ear {
+            Some(index) => {
+                let (a, b, c) = corner(index);
+                triangles.push(Triangle2d::new(a, b, c));
+                indices.remove(index);
+            }
+            None => {
+                // only collinear corners left, drop one and try again
+                let Some(index) = (0..len).find(|index| {
+                    let (a, b, c) = corner(*index);
+                    (b - a).perp_dot(c - b).abs() <= f32::EPSILON
+                }) else {
+                    break;
+                };
+                indices.remove(index);
+            }
+        }
+    }
+
+    if let [a, b, c] = indices[..] {
+        let (a, b, c) = (vertices[a], vertices[b], vertices[c]);
+        if (b - a).perp_dot(c - b).abs() > f32::EPSILON {
+            triangles.push(Triangle2d::new(a, b, c));
+        }
+    }
+
+    triangles
+}
+
+fn sample_shape(
+    shape: &impl ShapeSample<Output = Vec2>,
+    rng: &mut ParticleRng,
+    edge: bool,
+) -> Vec2 {
+    if edge {
+        shape.sample_boundary(rng)
+    } else {
+        shape.sample_interior(rng)
+    }
+}
+
+fn in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    (b - a).perp_dot(p - a) >= 0. && (c - b).perp_dot(p - b) >= 0. && (a - c).perp_dot(p - c) >= 0.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concave_polygon_triangulates_to_full_area() {
+        // L shape, 3 unit squares
+        let polygon = EmissionPolygon::new([
+            Vec2::new(0., 0.),
+            Vec2::new(2., 0.),
+            Vec2::new(2., 1.),
+            Vec2::new(1., 1.),
+            Vec2::new(1., 2.),
+            Vec2::new(0., 2.),
+        ]);
+
+        assert_eq!(polygon.triangles.len(), 4);
+        assert!((polygon.areas.last().unwrap() - 3.).abs() < 1e-5);
+
+        let mut rng = ParticleRng::new(7);
+        for _ in 0..1000 {
+            let p = polygon.sample_interior(&mut rng);
+            assert!(p.cmpge(Vec2::ZERO).all() && p.cmple(Vec2::splat(2.)).all());
+            assert!(
+                !(p.x > 1.0001 && p.y > 1.0001),
+                "{p} outside of the L shape"
+            );
+        }
+    }
+
+    #[test]
+    fn edge_normals_point_outwards() {
+        let rectangle = EmissionShape::Rectangle {
+            half_size: Vec2::new(2., 1.),
+        };
+        assert_eq!(rectangle.normal_at(Vec2::new(1.9, 0.)), Vec2::X);
+        assert_eq!(rectangle.normal_at(Vec2::new(0., -0.9)), Vec2::NEG_Y);
+
+        let annulus = EmissionShape::Annulus {
+            inner_radius: 1.,
+            outer_radius: 2.,
+        };
+        assert_eq!(annulus.normal_at(Vec2::new(1.1, 0.)), Vec2::NEG_X);
+
+        for vertices in [
+            vec![Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y],
+            vec![Vec2::Y, Vec2::ONE, Vec2::X, Vec2::ZERO],
+        ] {
+            let polygon = EmissionShape::Polygon(EmissionPolygon::new(vertices));
+            let normal = polygon.normal_at(Vec2::new(0.5, 0.1));
+            assert!(normal.abs_diff_eq(Vec2::NEG_Y, 1e-5), "{normal}");
+        }
+    }
+
+    #[test]
+    fn image_mask_reads_opaque_pixels() {
+        use bevy_asset::RenderAssetUsages;
+        use bevy_render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+        // 3x3, only the top row is opaque
+        let mut data = vec![0u8; 3 * 3 * 4];
+        for pixel in data.chunks_exact_mut(4).take(3) {
+            pixel.copy_from_slice(&[255, 0, 0, 255]);
+        }
+        let image = Image::new(
+            Extent3d {
+                width: 3,
+                height: 3,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8Unorm,
+            RenderAssetUsages::default(),
+        );
+
+        let mut mask = ImageMask::default().with_scale(2.).with_color();
+        mask.prepare(&image);
+        assert_eq!(mask.pixels.len(), 3);
+        assert_eq!(mask.edge_pixels.len(), 3);
+        assert_eq!(mask.pixels[1].position, Vec2::new(0., 2.));
+
+        let (position, color) = mask.sample(&mut ParticleRng::new(7), false);
+        assert!((position.y - 2.).abs() <= 1.);
+        assert_eq!(color, Some(LinearRgba::RED));
+
+        // masks of the same image share the read pixels
+        let mut cache = ImageMaskCache::default();
+        let (mut first, mut second) = (ImageMask::default(), ImageMask::default());
+        first.prepare_cached(&image, &mut cache);
+        second.prepare_cached(&image, &mut cache);
+        assert!(Arc::ptr_eq(&first.pixels, &second.pixels));
+        let mut other_scale = ImageMask::default().with_scale(2.);
+        other_scale.prepare_cached(&image, &mut cache);
+        assert_eq!(cache.0.len(), 2);
+        cache.remove_image(first.image.id());
+        assert!(cache.0.is_empty());
+
+        // nothing above the threshold still counts as read
+        let mut mask = ImageMask::default().with_alpha_threshold(2.);
+        assert!(!mask.is_ready());
+        mask.prepare(&image);
+        assert!(mask.pixels.is_empty());
+        assert!(mask.is_ready());
+    }
+
+    #[test]
+    fn negative_arc_angle_samples_a_line() {
+        let arc = EmissionShape::Arc {
+            radius: 2.,
+            half_angle: -1.,
+        };
+        let mut rng = ParticleRng::new(7);
+        for edge in [true, false] {
+            let (position, _) = arc.sample(&mut rng, edge);
+            assert!(position.x.abs() < 1e-5, "{position}");
+        }
+    }
+
+    #[test]
+    fn primitive_shapes_sample_inside_and_on_the_edge() {
+        let mut rng = ParticleRng::new(3);
+        let annulus = EmissionShape::Annulus {
+            inner_radius: 1.,
+            outer_radius: 2.,
+        };
+        let rectangle = EmissionShape::Rectangle {
+            half_size: Vec2::new(2., 1.),
+        };
+        for _ in 0..1000 {
+            let (inside, _) = annulus.sample(&mut rng, false);
+            assert!((1. ..=2.0001).contains(&inside.length()), "{inside}");
+            let (edge, _) = annulus.sample(&mut rng, true);
+            let distance = edge.length();
+            assert!((distance - 1.).abs() < 1e-4 || (distance - 2.).abs() < 1e-4);
+
+            let (inside, _) = rectangle.sample(&mut rng, false);
+            assert!(inside.abs().cmple(Vec2::new(2., 1.)).all(), "{inside}");
+            let (edge, _) = rectangle.sample(&mut rng, true);
+            assert!(
+                (edge.x.abs() - 2.).abs() < 1e-5 || (edge.y.abs() - 1.).abs() < 1e-5,
+                "{edge}"
+            );
+        }
+
+        // degenerate shapes sample, instead of panicking on empty ranges
+        for shape in [
+            EmissionShape::Annulus {
+                inner_radius: 2.,
+                outer_radius: 2.,
+            },
+            EmissionShape::Annulus {
+                inner_radius: 3.,
+                outer_radius: 1.,
+            },
+            EmissionShape::Rectangle {
+                half_size: Vec2::new(-1., 0.),
+            },
+            EmissionShape::Circle(0.),
+        ] {
+            shape.sample(&mut rng, false);
+            shape.sample(&mut rng, true);
+        }
+    }
+
+    #[test]
+    fn polygon_deserializes_from_vertices() {
+        let shape: EmissionShape =
+            ron::de::from_str("Polygon([(0, 0), (1, 0), (1, 1), (0, 1)])").unwrap();
+        let EmissionShape::Polygon(polygon) = &shape else {
+            panic!("expected polygon");
+        };
+        assert_eq!(polygon.triangles.len(), 2);
+        assert_eq!(
+            ron::ser::to_string(&shape).unwrap(),
+            "Polygon([(0.0,0.0),(1.0,0.0),(1.0,1.0),(0.0,1.0)])"
+        );
+    }
+}
@@ -1,9 +1,17 @@
 use super::{Particle2dMaterial, PARTICLE_SPRITE_FRAG};
-use bevy_asset::{Asset, Handle};
-use bevy_image::Image;
-use bevy_math::UVec4;
+use bevy_asset::{Asset, AssetEvent, AssetId, Assets, Handle};
+use bevy_ecs::{
+    message::MessageReader,
+    system::{Res, ResMut},
+};
+use bevy_image::{Image, TextureAtlasLayout};
+use bevy_math::{UVec4, Vec4};
 use bevy_reflect::TypePath;
-use bevy_render::render_resource::AsBindGroup;
+use bevy_render::render_resource::{AsBindGroup, ShaderType};
+
+/// Frames of a texture atlas beyond this count are ignored, with a warning.
+/// Every sprite material uploads this many rects.
+pub const MAX_ATLAS_FRAMES: usize = 64;
 
 /// Sprite Material lets you add textures and animations
 /// to particles.
@@ -14,6 +22,47 @@ pub struct SpriteParticle2dMaterial {
     pub texture: Option<Handle<Image>>,
     #[uniform(2)]
     frame_data: UVec4,
+    #[uniform(3)]
+    atlas: AtlasRects,
+    /// the frames are copied from this layout, whenever it changes
+    atlas_layout: Option<Handle<TextureAtlasLayout>>,
+}
+
+/// uv rects of the atlas frames, the grid of `frame_data` is used without frames
+#[derive(ShaderType, Clone, Debug, PartialEq)]
+struct AtlasRects {
+    count: u32,
+    /// min xy, max zw
+    rects: [Vec4; MAX_ATLAS_FRAMES],
+}
+
+impl Default for AtlasRects {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            rects: [Vec4::ZERO; MAX_ATLAS_FRAMES],
+        }
+    }
+}
+
+impl AtlasRects {
+    fn from_layout(layout: &TextureAtlasLayout) -> Self {
+        let mut atlas = Self::default();
+        let size = layout.size.as_vec2().max(bevy_math::Vec2::ONE);
+        for (rect, frame) in atlas.rects.iter_mut().zip(layout.textures.iter()) {
+            let min = frame.min.as_vec2() / size;
+            let max = frame.max.as_vec2() / size;
+            *rect = Vec4::new(min.x, min.y, max.x, max.y);
+        }
+        if layout.textures.len() > MAX_ATLAS_FRAMES {
+            bevy_log::warn!(
+                "texture atlas has {} frames, particles only use the first {MAX_ATLAS_FRAMES}",
+                layout.textures.len()
+            );
+        }
+        atlas.count = layout.textures.len().min(MAX_ATLAS_FRAMES) as u32;
+        atlas
+    }
 }
 
 impl Default for SpriteParticle2dMaterial {
@@ -21,6 +70,8 @@ impl Default for SpriteParticle2dMaterial {
         Self {
             texture: None,
             frame_data: UVec4::ONE,
+            atlas: AtlasRects::default(),
+            atlas_layout: None,
         }
     }
 }
@@ -30,6 +81,8 @@ impl SpriteParticle2dMaterial {
         Self {
             texture: Some(texture),
             frame_data: UVec4::new(max_hframes, max_vframes, 0, 0),
+            atlas: AtlasRects::default(),
+            atlas_layout: None,
         }
     }
 
@@ -37,6 +90,65 @@ impl SpriteParticle2dMaterial {
         Self {
             texture: Some(texture),
             frame_data: UVec4::new(1, 1, 0, 0),
+            atlas: AtlasRects::default(),
+            atlas_layout: None,
+        }
+    }
+
+    /// frames are the regions of a packed atlas, instead of a grid.
+    /// Supports up to `MAX_ATLAS_FRAMES` regions, the frames follow changes of the layout.
+    pub fn from_atlas(texture: Handle<Image>, layout: Handle<TextureAtlasLayout>) -> Self {
+        Self {
+            texture: Some(texture),
+            frame_data: UVec4::new(1, 1, 0, 0),
+            atlas: AtlasRects::default(),
+            atlas_layout: Some(layout),
+        }
+    }
+}
+
+/// copies the frames of atlas layouts into the materials, that use them
+pub(crate) fn sync_atlas_rects(
+    mut layout_events: MessageReader<AssetEvent<TextureAtlasLayout>>,
+    mut material_events: MessageReader<AssetEvent<SpriteParticle2dMaterial>>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+    mut materials: ResMut<Assets<SpriteParticle2dMaterial>>,
+) {
+    let changed_layouts: Vec<AssetId<TextureAtlasLayout>> = layout_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Added { id }
+            | AssetEvent::Modified { id }
+            | AssetEvent::LoadedWithDependencies { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    let changed_materials: Vec<AssetId<SpriteParticle2dMaterial>> = material_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    if changed_layouts.is_empty() && changed_materials.is_empty() {
+        return;
+    }
+
+    let updates: Vec<(AssetId<SpriteParticle2dMaterial>, AtlasRects)> = materials
+        .iter()
+        .filter_map(|(id, material)| {
+            let layout = material.atlas_layout.as_ref()?;
+            if !changed_layouts.contains(&layout.id()) && !changed_materials.contains(&id) {
+                return None;
+            }
+            let atlas = AtlasRects::from_layout(layouts.get(layout)?);
+            // only touch materials, that differ, the write is a modification again
+            (atlas != material.atlas).then_some((id, atlas))
+        })
+        .collect();
+    for (id, atlas) in updates {
+        if let Some(mut material) = materials.get_mut(id) {
+            material.atlas = atlas;
         }
     }
 }
@@ -46,3 +158,66 @@ impl Particle2dMaterial for SpriteParticle2dMaterial {
         PARTICLE_SPRITE_FRAG.into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_math::{URect, UVec2};
+
+    #[test]
+    fn atlas_regions_become_uv_rects() {
+        let mut layout = TextureAtlasLayout::new_empty(UVec2::new(100, 50));
+        layout.add_texture(URect::new(0, 0, 50, 50));
+        layout.add_texture(URect::new(50, 0, 100, 25));
+
+        let atlas = AtlasRects::from_layout(&layout);
+        assert_eq!(atlas.count, 2);
+        assert_eq!(atlas.rects[0], Vec4::new(0., 0., 0.5, 1.));
+        assert_eq!(atlas.rects[1], Vec4::new(0.5, 0., 1., 0.5));
+        assert_eq!(u64::from(AtlasRects::min_size()), 16 + 16 * 64);
+    }
+
+    #[test]
+    fn atlas_materials_follow_their_layout() {
+        use bevy_ecs::{message::Messages, system::RunSystemOnce, world::World};
+
+        let mut world = World::new();
+        world.init_resource::<Assets<TextureAtlasLayout>>();
+        world.init_resource::<Assets<SpriteParticle2dMaterial>>();
+        world.init_resource::<Messages<AssetEvent<TextureAtlasLayout>>>();
+        world.init_resource::<Messages<AssetEvent<SpriteParticle2dMaterial>>>();
+
+        let mut layout = TextureAtlasLayout::new_empty(UVec2::new(100, 50));
+        layout.add_texture(URect::new(0, 0, 50, 50));
+        let layout = world
+            .resource_mut::<Assets<TextureAtlasLayout>>()
+            .add(layout);
+        let material = world
+            .resource_mut::<Assets<SpriteParticle2dMaterial>>()
+            .add(SpriteParticle2dMaterial::from_atlas(
+                Handle::default(),
+                layout.clone(),
+            ));
+        let count = |world: &World| {
+            world
+                .resource::<Assets<SpriteParticle2dMaterial>>()
+                .get(&material)
+                .unwrap()
+                .atlas
+                .count
+        };
+
+        world.write_message(AssetEvent::Added { id: material.id() });
+        world.run_system_once(sync_atlas_rects).unwrap();
+        assert_eq!(count(&world), 1);
+
+        world
+            .resource_mut::<Assets<TextureAtlasLayout>>()
+            .get_mut(&layout)
+            .unwrap()
+            .add_texture(URect::new(50, 0, 100, 25));
+        world.write_message(AssetEvent::Modified { id: layout.id() });
+        world.run_system_once(sync_atlas_rects).unwrap();
+        assert_eq!(count(&world), 2);
+    }
+}
@@ -0,0 +1,327 @@
+use crate::{
+    material::Particle2dMaterial,
+    update::{spawn_sub_particles, ParticleEffectInstance, ParticleSpawnerState, ParticleStore},
+    Particle2dEffect, ParticleEffectHandle, ParticleSpawner,
+};
+use bevy_asset::{AssetEvent, Assets, Handle};
+use bevy_color::LinearRgba;
+use bevy_ecs::{
+    change_detection::DetectChangesMut,
+    component::Component,
+    entity::Entity,
+    message::MessageReader,
+    query::{Changed, With, Without},
+    system::{Commands, Query, Res},
+};
+use bevy_math::Vec2;
+use bevy_transform::components::{GlobalTransform, Transform};
+use serde::{Deserialize, Serialize};
+
+/// sub emitters of sub emitters stop at this depth, self referencing effects would nest forever
+const MAX_DEPTH: usize = 4;
+
+/// Particles of another effect, created at the particles of this one.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct SubEmitter {
+    /// asset path of the child effect, loaded with this effect
+    pub effect: String,
+    #[serde(skip)]
+    pub handle: Handle<Particle2dEffect>,
+    pub trigger: SubEmitterTrigger,
+    /// child particles per trigger
+    pub count: u32,
+    /// fraction of the parent velocity added to the children
+    pub inherit_velocity: Option<f32>,
+    /// multiplies the start color of the children with the parent color
+    pub inherit_color: Option<bool>,
+}
+
+impl SubEmitter {
+    pub fn new(handle: Handle<Particle2dEffect>, trigger: SubEmitterTrigger, count: u32) -> Self {
+        Self {
+            effect: String::new(),
+            handle,
+            trigger,
+            count,
+            inherit_velocity: None,
+            inherit_color: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum SubEmitterTrigger {
+    Birth,
+    /// at the end of the lifetime, or when killed by a collision
+    Death,
+    Collision,
+    /// every n seconds, while the parent is alive
+    Interval(f32),
+}
+
+/// The spawner, that holds the particles of one sub emitter.
+/// It does not emit by itself and follows the transform of the parent.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+#[relationship(relationship_target = SubEmitterSpawners)]
+pub struct SubEmitterOf {
+    #[relationship]
+    pub parent: Entity,
+    /// position in `sub_emitters` of the parent effect
+    pub index: usize,
+}
+
+/// spawners of the sub emitters, despawned with the parent
+#[derive(Component, Debug)]
+#[relationship_target(relationship = SubEmitterOf, linked_spawn)]
+pub struct SubEmitterSpawners(Vec<Entity>);
+
+impl SubEmitterSpawners {
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+/// a triggered sub emitter, waiting for its particles
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SubEmit {
+    pub(crate) emitter: usize,
+    pub(crate) position: Vec2,
+    pub(crate) velocity: Vec2,
+    pub(crate) color: LinearRgba,
+}
+
+impl SubEmit {
+    fn new(emitter: usize, particles: &ParticleStore, index: usize) -> Self {
+        Self {
+            emitter,
+            position: Vec2::new(particles.position_x[index], particles.position_y[index]),
+            velocity: Vec2::new(particles.velocity_x[index], particles.velocity_y[index]),
+            color: LinearRgba::new(
+                particles.color_r[index],
+                particles.color_g[index],
+                particles.color_b[index],
+                particles.color_a[index],
+            ),
+        }
+    }
+}
+
+/// queues every sub emitter of the particle at `index`, that matches `trigger`
+pub(crate) fn trigger(
+    effect: &Particle2dEffect,
+    particles: &ParticleStore,
+    index: usize,
+    matches: impl Fn(SubEmitterTrigger) -> bool,
+    pending: &mut Vec<SubEmit>,
+) {
+    let Some(sub_emitters) = &effect.sub_emitters else {
+        return;
+    };
+    for (emitter, sub_emitter) in sub_emitters.iter().enumerate() {
+        if matches(sub_emitter.trigger) {
+            pending.push(SubEmit::new(emitter, particles, index));
+        }
+    }
+}
+
+/// queues interval sub emitters of particles, that passed an interval during the last `delta` seconds
+pub(crate) fn trigger_intervals(
+    effect: &Particle2dEffect,
+    particles: &ParticleStore,
+    delta: f32,
+    pending: &mut Vec<SubEmit>,
+) {
+    let Some(sub_emitters) = &effect.sub_emitters else {
+        return;
+    };
+    for (emitter, sub_emitter) in sub_emitters.iter().enumerate() {
+        let SubEmitterTrigger::Interval(interval) = sub_emitter.trigger else {
+            continue;
+        };
+        if interval <= 0. {
+            continue;
+        }
+        for index in 0..particles.len() {
+            let age = particles.duration_fraction[index] * particles.duration[index];
+            let before = (age - delta).max(0.);
+            if (age / interval).floor() > (before / interval).floor()
+                && particles.duration_fraction[index] < 1.
+            {
+                pending.push(SubEmit::new(emitter, particles, index));
+            }
+        }
+    }
+}
+
+/// spawns the spawners of the sub emitters, when the effect changed
+#[allow(clippy::type_complexity)]
+pub(crate) fn attach_sub_emitters<M: Particle2dMaterial>(
+    mut cmd: Commands,
+    spawners: Query<
+        (
+            Entity,
+            &ParticleSpawner<M>,
+            &ParticleEffectInstance,
+            &GlobalTransform,
+            Option<&SubEmitterSpawners>,
+        ),
+        Changed<ParticleEffectInstance>,
+    >,
+    sub_spawners: Query<(&SubEmitterOf, &ParticleEffectHandle)>,
+    parents: Query<&SubEmitterOf>,
+    effects: Res<Assets<Particle2dEffect>>,
+) {
+    for (entity, spawner, effect, global, attached) in spawners.iter() {
+        let sub_emitters = effect
+            .0
+            .as_ref()
+            .and_then(|effect| effect.sub_emitters.as_deref())
+            .unwrap_or_default();
+
+        // runtime changes of the effect keep the children alive
+        let attached = attached
+            .map(|attached| attached.0.as_slice())
+            .unwrap_or_default();
+        let unchanged = attached.len() == sub_emitters.len()
+            && attached.iter().all(|child| {
+                sub_spawners.get(*child).is_ok_and(|(of, handle)| {
+                    sub_emitters
+                        .get(of.index)
+                        .is_some_and(|sub_emitter| sub_emitter.handle == handle.0)
+                })
+            });
+        if unchanged {
+            continue;
+        }
+
+        cmd.entity(entity).despawn_related::<SubEmitterSpawners>();
+        if parents.iter_ancestors(entity).count() >= MAX_DEPTH {
+            continue;
+        }
+
+        // the children are complete right away, to catch the first triggers of the parent
+        for (index, sub_emitter) in sub_emitters.iter().enumerate() {
+            cmd.spawn((
+                ParticleSpawner(spawner.0.clone()),
+                ParticleEffectHandle(sub_emitter.handle.clone()),
+                ParticleEffectInstance(effects.get(&sub_emitter.handle).cloned()),
+                global.compute_transform(),
+                *global,
+                ParticleSpawnerState {
+                    active: false,
+                    ..Default::default()
+                },
+                SubEmitterOf {
+                    parent: entity,
+                    index,
+                },
+            ));
+        }
+    }
+}
+
+/// fills the effects of sub emitter spawners, that were attached
+/// before their effect finished loading
+pub(crate) fn load_sub_emitter_effects(
+    mut events: MessageReader<AssetEvent<Particle2dEffect>>,
+    mut sub_spawners: Query<
+        (&mut ParticleEffectInstance, &ParticleEffectHandle),
+        With<SubEmitterOf>,
+    >,
+    effects: Res<Assets<Particle2dEffect>>,
+) {
+    for event in events.read() {
+        let (AssetEvent::Added { id } | AssetEvent::LoadedWithDependencies { id }) = event else {
+            continue;
+        };
+        for (mut instance, handle) in sub_spawners.iter_mut() {
+            if instance.0.is_none() && handle.id() == *id {
+                instance.0 = effects.get(*id).cloned();
+            }
+        }
+    }
+}
+
+/// moves the sub emitter spawners to their parent,
+/// so attractors and accelerations of the children act around it
+#[allow(clippy::type_complexity)]
+pub(crate) fn follow_parents(
+    roots: Query<Entity, (With<SubEmitterSpawners>, Without<SubEmitterOf>)>,
+    children: Query<&SubEmitterSpawners>,
+    parents: Query<&SubEmitterOf>,
+    mut transforms: Query<(&mut Transform, &mut GlobalTransform)>,
+) {
+    for root in roots.iter() {
+        // breadth first, parents move before their children
+        for child in children.iter_descendants(root) {
+            let Ok(SubEmitterOf { parent, .. }) = parents.get(child) else {
+                continue;
+            };
+            let Ok((_, global)) = transforms.get(*parent) else {
+                continue;
+            };
+            let global = *global;
+            let Ok((mut transform, mut child_global)) = transforms.get_mut(child) else {
+                continue;
+            };
+            transform.set_if_neq(global.compute_transform());
+            child_global.set_if_neq(global);
+        }
+    }
+}
+
+/// moves the queued sub emitter triggers into the stores of the sub emitter spawners
+pub(crate) fn emit_sub_particles(
+    mut spawners: Query<(&mut ParticleStore, &mut ParticleSpawnerState)>,
+    effects: Query<&ParticleEffectInstance>,
+    parents: Query<(Entity, &SubEmitterSpawners)>,
+    sub_spawners: Query<&SubEmitterOf>,
+) {
+    for (parent, children) in parents.iter() {
+        let Ok((_, mut state)) = spawners.get_mut(parent) else {
+            continue;
+        };
+        if state.sub_emits.is_empty() {
+            continue;
+        }
+        let pending = std::mem::take(&mut state.bypass_change_detection().sub_emits);
+        let Some(sub_emitters) = effects
+            .get(parent)
+            .ok()
+            .and_then(|effect| effect.0.as_ref())
+            .and_then(|effect| effect.sub_emitters.as_ref())
+        else {
+            continue;
+        };
+
+        for child in children.iter() {
+            let Ok(SubEmitterOf { index, .. }) = sub_spawners.get(child) else {
+                continue;
+            };
+            let Some(sub_emitter) = sub_emitters.get(*index) else {
+                continue;
+            };
+            let Ok((mut store, mut state)) = spawners.get_mut(child) else {
+                continue;
+            };
+            let Some(effect) = effects.get(child).ok().and_then(|effect| effect.0.as_ref()) else {
+                continue;
+            };
+
+            for emit in pending.iter().filter(|emit| emit.emitter == *index) {
+                spawn_sub_particles(
+                    &mut store,
+                    &mut state,
+                    effect,
+                    emit.position,
+                    emit.velocity * sub_emitter.inherit_velocity.unwrap_or(0.),
+                    sub_emitter
+                        .inherit_color
+                        .unwrap_or(false)
+                        .then_some(emit.color),
+                    sub_emitter.count,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use crate::{curve::MultiCurve, update::ParticleStore};
+use bevy_color::LinearRgba;
+use bevy_math::{Vec2, Vec3, Vec4};
+use bevy_mesh::VertexBufferLayout;
+use bevy_reflect::Reflect;
+use bevy_render::render_resource::{ShaderType, VertexAttribute, VertexFormat, VertexStepMode};
+use serde::{Deserialize, Serialize};
+
+/// Renders every particle as a ribbon along its recent path,
+/// instead of a quad.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Trail {
+    /// recorded positions per particle
+    pub length: u32,
+    /// seconds between recorded positions, defaults to 1/60
+    pub interval: Option<f32>,
+    /// width from the head (0) to the tail (1), multiplies the y scale of the particle
+    pub width_curve: Option<MultiCurve<f32>>,
+    /// color from the head (0) to the tail (1), multiplies the color of the particle
+    pub color_curve: Option<MultiCurve<LinearRgba>>,
+    /// defaults to `Stretch`
+    pub uv_mode: Option<TrailUv>,
+}
+
+impl Trail {
+    pub fn new(length: u32) -> Self {
+        Self {
+            length,
+            interval: None,
+            width_curve: None,
+            color_curve: None,
+            uv_mode: None,
+        }
+    }
+}
+
+/// How the texture is laid along the ribbon. The texture x axis runs
+/// from the head to the tail, the y axis across.
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum TrailUv {
+    /// the texture covers the whole ribbon once
+    #[default]
+    Stretch,
+    /// the texture repeats every n world units
+    Tile(f32),
+}
+
+/// remembers the current position of every particle, once the interval passed.
+/// The history moves by `position_delta`, like the particles of relative spawners.
+pub(crate) fn record_trails(
+    particles: &mut ParticleStore,
+    trail: &Trail,
+    timer: &mut f32,
+    delta: f32,
+    position_delta: Vec3,
+) {
+    if position_delta != Vec3::ZERO {
+        let offset = position_delta.truncate();
+        for history in particles.trail.iter_mut() {
+            history.iter_mut().for_each(|point| *point += offset);
+        }
+    }
+
+    *timer += delta;
+    let interval = trail.interval.unwrap_or(1. / 60.).max(0.);
+    if *timer < interval {
+        return;
+    }
+    *timer = if interval > 0. { *timer % interval } else { 0. };
+
+    let length = trail.length as usize;
+    for index in 0..particles.len() {
+        let history = &mut particles.trail[index];
+        history.push_front(Vec2::new(
+            particles.position_x[index],
+            particles.position_y[index],
+        ));
+        history.truncate(length);
+    }
+}
+
+/// A vertex of a ribbon, not instanced.
+#[derive(Clone, Debug, Copy, ShaderType, Reflect)]
+pub struct TrailVertex {
+    position: Vec4,
+    uv_lifetime: Vec4,
+    color: Vec4,
+    frame: u32,
+}
+
+impl TrailVertex {
+    /// per vertex layout of the ribbon buffer, the stride includes the padding of the struct
+    pub(crate) fn vertex_layout() -> VertexBufferLayout {
+        VertexBufferLayout {
+            array_stride: Self::min_size().get(),
+            step_mode: VertexStepMode::Vertex,
+            attributes: vec![
+                // xyz position, z retains particle depth
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                // xy uv, zw lifetime
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 16,
+                    shader_location: 1,
+                },
+                // color
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 32,
+                    shader_location: 2,
+                },
+                // sprite frame
+                VertexAttribute {
+                    format: VertexFormat::Uint32,
+                    offset: 48,
+                    shader_location: 3,
+                },
+            ],
+        }
+    }
+}
+
+/// appends the ribbon of a particle as a triangle list.
+/// Ribbons need at least one recorded position.
+pub(crate) fn build_ribbon(
+    particles: &ParticleStore,
+    index: usize,
+    trail: &Trail,
+    vertices: &mut Vec<TrailVertex>,
+    indices: &mut Vec<u32>,
+) {
+    let head = Vec2::new(particles.position_x[index], particles.position_y[index]);
+    let history = &particles.trail[index];
+    if history.is_empty() {
+        return;
+    }
+
+    let color = Vec4::new(
+        particles.color_r[index],
+        particles.color_g[index],
+        particles.color_b[index],
+        particles.color_a[index],
+    );
+    let width = particles.scale_y[index];
+    let lifetime = (
+        particles.duration_fraction[index],
+        particles.duration[index],
+    );
+    let depth = particles.position_z[index];
+    let frame = particles.frame[index];
+
+    let last = history.len();
+    let first_vertex = vertices.len() as u32;
+    let mut distance = 0.;
+    let mut side = Vec2::Y;
+    let mut previous = head;
+    let mut points = std::iter::once(head)
+        .chain(history.iter().copied())
+        .peekable();
+    let mut point = 0;
+    while let Some(position) = points.next() {
+        let progress = point as f32 / last as f32;
+        distance += position.distance(previous);
+
+        // keep the previous side, on points without movement
+        let next = points.peek().copied().unwrap_or(position);
+        let tangent = previous - next;
+        side = tangent.perp().try_normalize().unwrap_or(side);
+
+        let half_width = width
+            * 0.5
+            * trail
+                .width_curve
+                .as_ref()
+                .map_or(1., |curve| curve.lerp(progress));
+        let color = trail.color_curve.as_ref().map_or(color, |curve| {
+            let tint = curve.lerp(progress);
+            color * Vec4::new(tint.red, tint.green, tint.blue, tint.alpha)
+        });
+        let u = match trail.uv_mode.unwrap_or_default() {
+            TrailUv::Stretch => progress,
+            TrailUv::Tile(length) => distance / length.max(f32::EPSILON),
+        };
+
+        for (offset, v) in [(side * half_width, 0.), (-side * half_width, 1.)] {
+            let position = position + offset;
+            vertices.push(TrailVertex {
+                position: Vec4::new(position.x, position.y, depth, 0.),
+                uv_lifetime: Vec4::new(u, v, lifetime.0, lifetime.1),
+                color,
+                frame,
+            });
+        }
+        previous = position;
+        point += 1;
+    }
+
+    for segment in 0..last as u32 {
+        let start = first_vertex + segment * 2;
+        indices.extend([start, start + 1, start + 2, start + 1, start + 3, start + 2]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> ParticleStore {
+        let mut store = ParticleStore::default();
+        store.position_x.push(0.);
+        store.position_y.push(0.);
+        store.position_z.push(2.);
+        store.scale_y.push(4.);
+        store.duration.push(1.);
+        store.duration_fraction.push(0.5);
+        store.color_r.push(1.);
+        store.color_g.push(1.);
+        store.color_b.push(1.);
+        store.color_a.push(1.);
+        store.frame.push(3);
+        store.trail.push(Default::default());
+        store
+    }
+
+    #[test]
+    fn trails_record_on_interval() {
+        let trail = Trail {
+            interval: Some(0.1),
+            ..Trail::new(3)
+        };
+        let mut particles = store();
+        let mut timer = 0.;
+        for frame in 0..10 {
+            particles.position_x[0] = frame as f32;
+            record_trails(&mut particles, &trail, &mut timer, 0.05, Vec3::ZERO);
+        }
+        let history: Vec<f32> = particles.trail[0].iter().map(|point| point.x).collect();
+        assert_eq!(history, [9., 7., 5.]);
+
+        // relative spawners carry the history along
+        record_trails(
+            &mut particles,
+            &trail,
+            &mut timer,
+            0.,
+            Vec3::new(1., 2., 0.),
+        );
+        let history: Vec<Vec2> = particles.trail[0].iter().copied().collect();
+        assert_eq!(
+            history,
+            [Vec2::new(10., 2.), Vec2::new(8., 2.), Vec2::new(6., 2.)]
+        );
+    }
+
+    #[test]
+    fn ribbons_follow_the_path() {
+        let trail = Trail {
+            width_curve: Some(
+                MultiCurve::new()
+                    .with_point(1., 0., None)
+                    .with_point(0., 1., None),
+            ),
+            uv_mode: Some(TrailUv::Tile(5.)),
+            ..Trail::new(4)
+        };
+        let mut particles = store();
+        particles.trail[0].extend([Vec2::new(-10., 0.), Vec2::new(-20., 0.)]);
+
+        let (mut vertices, mut indices) = (vec![], vec![]);
+        build_ribbon(&particles, 0, &trail, &mut vertices, &mut indices);
+        assert_eq!(vertices.len(), 6);
+        assert_eq!(indices, [0, 1, 2, 1, 3, 2, 2, 3, 4, 3, 5, 4]);
+
+        // head is full width across the path, the tail narrows to a point
+        assert_eq!(vertices[0].position, Vec4::new(0., 2., 2., 0.));
+        assert_eq!(vertices[1].position, Vec4::new(0., -2., 2., 0.));
+        assert_eq!(vertices[4].position, vertices[5].position);
+        assert_eq!(vertices[3].uv_lifetime, Vec4::new(2., 1., 0.5, 1.));
+        assert_eq!(vertices[3].frame, 3);
+        assert_eq!(
+            TrailVertex::vertex_layout().array_stride,
+            TrailVertex::min_size().get()
+        );
+
+        // nothing to draw without history
+        particles.trail[0].clear();
+        build_ribbon(&particles, 0, &trail, &mut vertices, &mut indices);
+        assert_eq!(vertices.len(), 6);
+    }
+}
@@ -1,25 +1,40 @@
-use super::{prelude::EmissionShape, Particle2dEffect, ParticleEffectHandle};
-use crate::values::Random;
-use bevy_asset::Assets;
+use super::{Burst, ColorCurveMode, Particle2dEffect, ParticleEffectHandle, RotationMode};
+use crate::{
+    collision::{collide_particles, Colliders, ParticleColliders, ParticleCollisionQueries},
+    curve::{CurveKnots, MultiCurve},
+    force::{apply_force_fields, ParticleForceFields, ParticleForceLayers},
+    messages::MessageBuffer,
+    rng::{ParticleRng, ParticleSeed},
+    shape::{DirectionMode, EmissionShape, ImageMaskCache},
+    sub_emitter::{self, SubEmit, SubEmitterSpawners, SubEmitterTrigger},
+    trail::record_trails,
+    values::{Random, Rval},
+};
+use bevy_asset::{AssetEvent, Assets};
 use bevy_camera::primitives::Aabb;
 use bevy_color::LinearRgba;
 use bevy_derive::{Deref, DerefMut};
 use bevy_ecs::{
     component::Component,
     entity::Entity,
-    query::{Added, Without},
+    message::MessageReader,
+    query::{Added, Changed, Without},
     reflect::ReflectComponent,
-    system::{Commands, Query, Res},
+    system::{Commands, Query, Res, ResMut},
+    world::FilteredResources,
 };
-use bevy_math::{EulerRot, Vec2, Vec3};
+use bevy_image::Image;
+use bevy_math::{EulerRot, Quat, Vec2, Vec3};
 use bevy_reflect::{prelude::ReflectDefault, Reflect};
 use bevy_time::{Time, Timer, TimerMode, Virtual};
 use bevy_transform::components::{GlobalTransform, Transform};
-use std::time::Duration;
+use rand::Rng;
+use std::{collections::VecDeque, ops::Range, time::Duration};
 use wide::f32x8;
 
 /// Tag Component, deactivates spawner after the first
-/// spawning of particles
+/// spawning of particles. Effects with a `duration` deactivate
+/// once the emitter cycle is done instead.
 #[derive(Component, Default, Debug, Reflect)]
 #[reflect(Component, Debug, Default)]
 pub enum OneShot {
@@ -28,13 +43,50 @@ pub enum OneShot {
     Despawn,
 }
 
+/// What happens to new particles, once a spawner
+/// reached `max_particles`.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum OverflowPolicy {
+    /// new particles are not spawned
+    #[default]
+    Skip,
+    /// replaces the particle, that lived the longest
+    RecycleOldest,
+    /// replaces the particle with the least lifetime left
+    RecycleNearestDeath,
+}
+
 /// Spawner states controls the spawner
 #[derive(Component, Clone, Debug, Reflect)]
 pub struct ParticleSpawnerState {
     pub max_particles: u32,
+    pub overflow: OverflowPolicy,
     pub active: bool,
     pub timer: Timer,
     pub previous_position: Option<Vec3>,
+    /// seconds the spawner is active, including the start delay.
+    /// Resets, when a non looping emitter finished and wraps
+    /// around every cycle of a looping one.
+    pub elapsed: f32,
+    /// world units moved since the last distance based emission
+    pub distance_travelled: f32,
+    /// fraction of a particle, carried over to the next frame
+    pub spawn_accumulator: f32,
+    /// random stream of this spawner, see `ParticleSeed`
+    pub rng: ParticleRng,
+    /// seconds since the spawner exists, scrolls the turbulence
+    pub time: f32,
+    /// collected messages, see `ParticleMessages`
+    #[reflect(ignore)]
+    pub(crate) messages: MessageBuffer,
+    /// triggered sub emitters of this frame
+    #[reflect(ignore)]
+    pub(crate) sub_emits: Vec<SubEmit>,
+    /// seconds since the last recorded trail position
+    pub(crate) trail_timer: f32,
+    /// particle positions before the update, to find crossed collider surfaces
+    #[reflect(ignore)]
+    pub(crate) previous_positions: (Vec<f32>, Vec<f32>),
 }
 
 /// A clone of the asset, unique to each spawner
@@ -49,14 +101,36 @@ impl Default for ParticleSpawnerState {
         Self {
             active: true,
             max_particles: u32::MAX,
+            overflow: OverflowPolicy::Skip,
             timer: Timer::new(Duration::ZERO, TimerMode::Repeating),
             previous_position: None,
+            elapsed: 0.,
+            distance_travelled: 0.,
+            spawn_accumulator: 0.,
+            rng: ParticleRng::default(),
+            time: 0.,
+            messages: MessageBuffer::default(),
+            sub_emits: Vec::new(),
+            trail_timer: 0.,
+            previous_positions: (Vec::new(), Vec::new()),
+        }
+    }
+}
+
+impl ParticleSpawnerState {
+    /// limits the spawner to `max_particles`. The particle store
+    /// is preallocated to this size.
+    pub fn with_capacity(max_particles: u32, overflow: OverflowPolicy) -> Self {
+        Self {
+            max_particles,
+            overflow,
+            ..Default::default()
         }
     }
 }
 
 /// Component for storing particle data
-#[derive(Component, Default, Clone, Reflect)]
+#[derive(Component, Default, Clone, PartialEq, Reflect)]
 pub struct ParticleStore {
     pub(crate) position_x: Vec<f32>,
     pub(crate) position_y: Vec<f32>,
@@ -65,6 +139,8 @@ pub struct ParticleStore {
     pub(crate) scale_x: Vec<f32>,
     pub(crate) scale_y: Vec<f32>,
     pub(crate) scale_z: Vec<f32>,
+    /// length multiplier along the x axis, from the velocity stretch
+    pub(crate) stretch: Vec<f32>,
     pub(crate) duration: Vec<f32>,
     pub(crate) duration_fraction: Vec<f32>,
     pub(crate) velocity_x: Vec<f32>,
@@ -75,7 +151,12 @@ pub struct ParticleStore {
     pub(crate) color_g: Vec<f32>,
     pub(crate) color_b: Vec<f32>,
     pub(crate) color_a: Vec<f32>,
+    /// color at birth, tinted by a multiplying color curve
+    pub(crate) start_color: Vec<LinearRgba>,
+    /// sprite sheet frame, `NO_FRAME` without a sprite animation
     pub(crate) frame: Vec<u32>,
+    /// offset into the frame range of the sprite animation
+    pub(crate) frame_start: Vec<u32>,
     pub(crate) linear_acceleration: Vec<f32>,
     pub(crate) linear_damp: Vec<f32>,
     pub(crate) angular_acceleration: Vec<f32>,
@@ -84,6 +165,10 @@ pub struct ParticleStore {
     pub(crate) gravity_x: Vec<f32>,
     pub(crate) gravity_y: Vec<f32>,
     pub(crate) gravity_z: Vec<f32>,
+    pub(crate) radial_acceleration: Vec<f32>,
+    pub(crate) tangential_acceleration: Vec<f32>,
+    /// recent positions, newest first. Only recorded for effects with a trail.
+    pub(crate) trail: Vec<VecDeque<Vec2>>,
 }
 
 impl ParticleStore {
@@ -95,6 +180,52 @@ impl ParticleStore {
         self.duration.is_empty()
     }
 
+    pub fn capacity(&self) -> usize {
+        self.duration.capacity()
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        macro_rules! reserve {
+            ($($field:ident),+ $(,)?) => {
+                $(self.$field.reserve_exact(additional);)+
+            };
+        }
+        reserve!(
+            position_x,
+            position_y,
+            position_z,
+            rotation,
+            scale_x,
+            scale_y,
+            scale_z,
+            stretch,
+            duration,
+            duration_fraction,
+            velocity_x,
+            velocity_y,
+            velocity_z,
+            angular_velocity,
+            color_r,
+            color_g,
+            color_b,
+            color_a,
+            start_color,
+            frame,
+            frame_start,
+            linear_acceleration,
+            linear_damp,
+            angular_acceleration,
+            angular_damp,
+            gravity_speed,
+            gravity_x,
+            gravity_y,
+            gravity_z,
+            radial_acceleration,
+            tangential_acceleration,
+            trail,
+        );
+    }
+
     pub fn clear(&mut self) {
         macro_rules! clear {
             ($($field:ident),+ $(,)?) => {
@@ -109,6 +240,7 @@ impl ParticleStore {
             scale_x,
             scale_y,
             scale_z,
+            stretch,
             duration,
             duration_fraction,
             velocity_x,
@@ -119,7 +251,9 @@ impl ParticleStore {
             color_g,
             color_b,
             color_a,
+            start_color,
             frame,
+            frame_start,
             linear_acceleration,
             linear_damp,
             angular_acceleration,
@@ -128,6 +262,9 @@ impl ParticleStore {
             gravity_x,
             gravity_y,
             gravity_z,
+            radial_acceleration,
+            tangential_acceleration,
+            trail,
         );
     }
 
@@ -145,6 +282,8 @@ impl ParticleStore {
         angular_damp: f32,
         gravity_speed: f32,
         gravity_direction: Vec3,
+        radial_acceleration: f32,
+        tangential_acceleration: f32,
     ) {
         self.position_x.push(transform.translation.x);
         self.position_y.push(transform.translation.y);
@@ -154,6 +293,7 @@ impl ParticleStore {
         self.scale_x.push(transform.scale.x);
         self.scale_y.push(transform.scale.y);
         self.scale_z.push(transform.scale.z);
+        self.stretch.push(1.0);
         self.duration.push(duration);
         self.duration_fraction.push(0.0);
         self.velocity_x.push(velocity.x);
@@ -164,7 +304,9 @@ impl ParticleStore {
         self.color_g.push(color.green);
         self.color_b.push(color.blue);
         self.color_a.push(color.alpha);
-        self.frame.push(0);
+        self.start_color.push(color);
+        self.frame.push(NO_FRAME);
+        self.frame_start.push(0);
         self.linear_acceleration.push(linear_acceleration);
         self.linear_damp.push(linear_damp);
         self.angular_acceleration.push(angular_acceleration);
@@ -173,6 +315,9 @@ impl ParticleStore {
         self.gravity_x.push(gravity_direction.x);
         self.gravity_y.push(gravity_direction.y);
         self.gravity_z.push(gravity_direction.z);
+        self.radial_acceleration.push(radial_acceleration);
+        self.tangential_acceleration.push(tangential_acceleration);
+        self.trail.push(VecDeque::new());
     }
 
     fn swap_remove(&mut self, index: usize) {
@@ -189,6 +334,7 @@ impl ParticleStore {
             scale_x,
             scale_y,
             scale_z,
+            stretch,
             duration,
             duration_fraction,
             velocity_x,
@@ -199,7 +345,9 @@ impl ParticleStore {
             color_g,
             color_b,
             color_a,
+            start_color,
             frame,
+            frame_start,
             linear_acceleration,
             linear_damp,
             angular_acceleration,
@@ -208,9 +356,51 @@ impl ParticleStore {
             gravity_x,
             gravity_y,
             gravity_z,
+            radial_acceleration,
+            tangential_acceleration,
+            trail,
         );
     }
 
+    /// frees a slot for a new particle, if the store is full.
+    /// Returns false, if the particle should be skipped.
+    /// recycles a particle of a full spawner. The swept `previous` positions
+    /// of colliding spawners stay in line with the particles.
+    fn make_room(
+        &mut self,
+        max_particles: u32,
+        overflow: OverflowPolicy,
+        previous: &mut (Vec<f32>, Vec<f32>),
+    ) -> bool {
+        if self.len() < max_particles as usize {
+            return true;
+        }
+
+        let index = match overflow {
+            OverflowPolicy::Skip => None,
+            OverflowPolicy::RecycleOldest => (0..self.len()).max_by(|a, b| {
+                let age = |i: usize| self.duration_fraction[i] * self.duration[i];
+                age(*a).total_cmp(&age(*b))
+            }),
+            OverflowPolicy::RecycleNearestDeath => (0..self.len()).min_by(|a, b| {
+                let left = |i: usize| (1. - self.duration_fraction[i]) * self.duration[i];
+                left(*a).total_cmp(&left(*b))
+            }),
+        };
+
+        match index {
+            Some(index) => {
+                if previous.0.len() == self.len() {
+                    previous.0.swap_remove(index);
+                    previous.1.swap_remove(index);
+                }
+                self.swap_remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
     fn remove_expired(&mut self) {
         for index in (0..self.len()).rev() {
             if self.duration_fraction[index] >= 1.0 {
@@ -238,19 +428,97 @@ pub(crate) fn clone_effect(
         });
 }
 
+/// reads the pixels of image emission masks, once the image is loaded
+/// and again whenever it changes
+pub(crate) fn prepare_image_masks(
+    mut particle_spawners: Query<&mut ParticleEffectInstance>,
+    mut image_events: MessageReader<AssetEvent<Image>>,
+    images: Res<Assets<Image>>,
+    mut cache: ResMut<ImageMaskCache>,
+) {
+    let mut modified = Vec::new();
+    for event in image_events.read() {
+        let (AssetEvent::Modified { id } | AssetEvent::Removed { id } | AssetEvent::Unused { id }) =
+            event
+        else {
+            continue;
+        };
+        cache.remove_image(*id);
+        if matches!(event, AssetEvent::Modified { .. }) {
+            modified.push(*id);
+        }
+    }
+
+    particle_spawners.iter_mut().for_each(|mut instance| {
+        let Some(Particle2dEffect {
+            emission_shape: EmissionShape::Image(mask),
+            ..
+        }) = &instance.0
+        else {
+            return;
+        };
+
+        if mask.is_ready() && !modified.contains(&mask.image.id()) {
+            return;
+        }
+
+        let Some(image) = images.get(&mask.image) else {
+            return;
+        };
+
+        if let Some(Particle2dEffect {
+            emission_shape: EmissionShape::Image(mask),
+            ..
+        }) = &mut instance.0
+        {
+            mask.prepare_cached(image, &mut cache);
+        }
+    });
+}
+
+/// restarts the random stream of seeded spawners
+pub(crate) fn apply_seed(
+    mut particle_spawners: Query<(&ParticleSeed, &mut ParticleSpawnerState), Changed<ParticleSeed>>,
+) {
+    particle_spawners
+        .iter_mut()
+        .for_each(|(seed, mut state)| state.rng = ParticleRng::new(seed.0));
+}
+
 pub(crate) fn remove_finished_spawner(
     mut cmd: Commands,
-    spawner: Query<(Entity, &ParticleStore, &ParticleSpawnerState, &OneShot)>,
+    spawner: Query<(
+        Entity,
+        &ParticleStore,
+        &ParticleSpawnerState,
+        &OneShot,
+        Option<&SubEmitterSpawners>,
+    )>,
+    sub_spawners: Query<&ParticleStore>,
 ) {
     spawner
         .iter()
-        .for_each(|(entity, store, controller, one_shot)| {
-            if matches!(one_shot, OneShot::Despawn) && !controller.active && store.is_empty() {
+        .for_each(|(entity, store, controller, one_shot, sub_emitters)| {
+            // particles of sub emitters outlive their parent particles
+            let sub_emitters_done = controller.sub_emits.is_empty()
+                && sub_emitters.is_none_or(|sub_emitters| {
+                    sub_emitters.iter().all(|sub_spawner| {
+                        sub_spawners
+                            .get(sub_spawner)
+                            .is_ok_and(ParticleStore::is_empty)
+                    })
+                });
+            if matches!(one_shot, OneShot::Despawn)
+                && !controller.active
+                && store.is_empty()
+                && sub_emitters_done
+            {
                 cmd.entity(entity).try_despawn();
             }
         })
 }
 
+#[allow(clippy::type_complexity)]
 pub(crate) fn update_spawner(
     mut particles: Query<(
         Entity,
@@ -258,57 +526,371 @@ pub(crate) fn update_spawner(
         &mut ParticleSpawnerState,
         &ParticleEffectInstance,
         &GlobalTransform,
+        Option<&ParticleForceLayers>,
     )>,
     one_shots: Query<&OneShot>,
+    force_fields: Res<ParticleForceFields>,
+    colliders: Res<ParticleColliders>,
+    collision_queries: Res<ParticleCollisionQueries>,
+    resources: FilteredResources,
     time: Res<Time<Virtual>>,
 ) {
+    let queries = collision_queries.collect(&resources);
     particles.par_iter_mut().for_each(
-        |(entity, mut store, mut state, effect_instance, transform)| {
-            if state.max_particles <= store.len() as u32 {
-                return;
-            }
-
+        |(entity, mut store, mut state, effect_instance, transform, layers)| {
             let Some(effect) = &effect_instance.0 else {
                 return;
             };
 
-            let transform = transform.compute_transform();
+            if let Some(layers) = layers {
+                apply_force_fields(&mut store, &force_fields.0, *layers, time.delta_secs());
+            }
+
+            tick_spawner(
+                &mut store,
+                &mut state,
+                effect,
+                &transform.compute_transform(),
+                time.delta(),
+                one_shots.get(entity).is_ok(),
+                Colliders::new(&colliders, &queries),
+            );
+        },
+    );
+}
 
+/// frame of particles without a sprite animation,
+/// the shader plays the whole sheet over their lifetime
+pub(crate) const NO_FRAME: u32 = u32::MAX;
+
+/// advances a single spawner by one frame. Simulates the existing
+/// particles and spawns new ones, pre-aged by the part of the frame
+/// they already lived.
+fn tick_spawner(
+    store: &mut ParticleStore,
+    state: &mut ParticleSpawnerState,
+    effect: &Particle2dEffect,
+    transform: &Transform,
+    delta: Duration,
+    one_shot: bool,
+    colliders: Colliders,
+) {
+    let delta_secs = delta.as_secs_f32();
+    let spawner_world_pos = transform.translation;
+    let travelled = state
+        .previous_position
+        .replace(spawner_world_pos)
+        .map_or(Vec3::ZERO, |previous| spawner_world_pos - previous);
+
+    // Handle relative positioning
+    let position_delta = if effect.relative_positioning.unwrap_or(false) {
+        travelled
+    } else {
+        Vec3::ZERO
+    };
+
+    // positions before the update, to find crossed surfaces. Relative
+    // spawners carry their particles along, that is no movement to sweep.
+    let collides = effect.collision.is_some() && !colliders.is_empty();
+    let (previous_x, previous_y) = &mut state.previous_positions;
+    previous_x.clear();
+    previous_y.clear();
+    if collides {
+        previous_x.extend(store.position_x.iter().map(|x| x + position_delta.x));
+        previous_y.extend(store.position_y.iter().map(|y| y + position_delta.y));
+    }
+
+    state.time += delta_secs;
+    update_particles(
+        store,
+        effect,
+        delta_secs,
+        spawner_world_pos,
+        position_delta,
+        state.time,
+    );
+
+    if let Some(trail) = &effect.trail {
+        record_trails(
+            store,
+            trail,
+            &mut state.trail_timer,
+            delta_secs,
+            position_delta,
+        );
+    }
+
+    // sub emitters, that were not picked up, belong to a removed sub spawner
+    state.sub_emits.clear();
+    sub_emitter::trigger_intervals(effect, store, delta_secs, &mut state.sub_emits);
+
+    // emitter time, relative to the end of the start delay
+    let delay = effect.start_delay.unwrap_or(0.).max(0.);
+    let from = state.elapsed - delay;
+    if state.active {
+        state.elapsed += delta_secs;
+    }
+    let to = state.elapsed - delay;
+
+    // preallocate limited spawners
+    if state.max_particles != u32::MAX && store.capacity() < state.max_particles as usize {
+        store.reserve(state.max_particles as usize - store.len());
+    }
+
+    let mut emitted = false;
+    if state.active && to > 0. {
+        // part of the frame the emitter was running
+        let active_secs = to.min(delta_secs);
+
+        emitted |= if let Some(step) = effect.spawn_distance.filter(|step| *step > 0.) {
+            emit_along_path(store, state, effect, transform, travelled, step, delta_secs)
+        } else if let Some(rate) = effect.spawn_per_second.filter(|rate| *rate > 0.) {
+            state.spawn_accumulator += rate * active_secs;
+            let count = state.spawn_accumulator.floor();
+            state.spawn_accumulator -= count;
+
+            // the n-th latest particle was born n / rate ago
+            for index in 0..count as u32 {
+                let age = (state.spawn_accumulator + index as f32) / rate;
+                spawn_particle(store, state, effect, transform, age.min(active_secs));
+            }
+            count > 0.
+        } else {
             state
                 .timer
                 .set_duration(Duration::from_secs_f32(effect.spawn_rate));
-            state.timer.tick(time.delta());
+            state.timer.tick(Duration::from_secs_f32(active_secs));
+
+            let mut periods = state.timer.times_finished_this_tick();
+            // zero duration timers finish endlessly, spawn once per frame
+            if effect.spawn_rate <= 0. || (one_shot && effect.duration.is_none()) {
+                periods = periods.min(1);
+            }
 
-            if state.timer.is_finished() && state.active {
+            // every period, that finished during this frame
+            for period in 0..periods {
+                let age = state.timer.elapsed_secs() + period as f32 * effect.spawn_rate;
                 for _ in 0..effect.spawn_amount {
-                    create_particle(&mut store, effect, &transform);
+                    spawn_particle(store, state, effect, transform, age.min(active_secs));
                 }
+            }
+            periods > 0
+        };
+
+        if let Some(bursts) = &effect.bursts {
+            let fires = burst_fires(bursts, from.max(0.), to, effect, &mut state.rng);
+            for (time, count) in fires {
+                for _ in 0..count {
+                    spawn_particle(store, state, effect, transform, to - time);
+                }
+                emitted |= count > 0;
+            }
+        }
 
-                if one_shots.get(entity).is_ok() {
+        let duration = effect.duration.filter(|duration| *duration > 0.);
+        if let Some(duration) = duration {
+            if to >= duration {
+                if effect.looping.unwrap_or(false) {
+                    // keep the emitter time small, to not lose precision
+                    state.elapsed -= (to / duration).floor() * duration;
+                } else {
+                    // finished, activating it again restarts the emitter
                     state.active = false;
+                    state.elapsed = 0.;
                 }
             }
-            let delta = time.delta_secs();
-            let spawner_world_pos = transform.translation;
-
-            // Handle relative positioning
-            let position_delta = if effect.relative_positioning.unwrap_or(false) {
-                let current_pos = spawner_world_pos;
-                let delta = if let Some(prev_pos) = state.previous_position {
-                    current_pos - prev_pos
-                } else {
-                    Vec3::ZERO
-                };
-                state.previous_position = Some(current_pos);
-                delta
-            } else {
-                Vec3::ZERO
-            };
+        }
+    }
 
-            update_particles(&mut store, effect, delta, spawner_world_pos, position_delta);
-            store.remove_expired();
-        },
+    // effects with a duration deactivate once they are done
+    if emitted && one_shot && effect.duration.is_none() {
+        state.active = false;
+    }
+
+    // after spawning, to sweep the new particles as well
+    if let Some(response) = effect.collision.filter(|_| collides) {
+        let collided = collide_particles(store, response, colliders, &state.previous_positions);
+        for (index, normal) in collided {
+            state.messages.collided(store, index, normal);
+            sub_emitter::trigger(
+                effect,
+                store,
+                index,
+                |trigger| trigger == SubEmitterTrigger::Collision,
+                &mut state.sub_emits,
+            );
+        }
+    }
+
+    if state.messages.enabled.died || effect.sub_emitters.is_some() {
+        for index in 0..store.len() {
+            if store.duration_fraction[index] >= 1.0 {
+                state.messages.died(store, index);
+                sub_emitter::trigger(
+                    effect,
+                    store,
+                    index,
+                    |trigger| trigger == SubEmitterTrigger::Death,
+                    &mut state.sub_emits,
+                );
+            }
+        }
+    }
+    store.remove_expired();
+}
+
+/// creates a particle, that already lived `age` seconds of this frame.
+/// Returns false, when the spawner is full.
+fn spawn_particle(
+    store: &mut ParticleStore,
+    state: &mut ParticleSpawnerState,
+    effect: &Particle2dEffect,
+    transform: &Transform,
+    age: f32,
+) -> bool {
+    if !store.make_room(
+        state.max_particles,
+        state.overflow,
+        &mut state.previous_positions,
+    ) {
+        return false;
+    }
+
+    create_particle(store, effect, transform, &mut state.rng);
+    let index = store.len() - 1;
+
+    // swept from the spawn position in its first frame
+    let (previous_x, previous_y) = &mut state.previous_positions;
+    if effect.collision.is_some() && previous_x.len() == index {
+        previous_x.push(store.position_x[index]);
+        previous_y.push(store.position_y[index]);
+    }
+    if age > 0. {
+        update_particle_scalar(
+            store,
+            effect,
+            index,
+            age,
+            transform.translation,
+            Vec3::ZERO,
+            state.time,
+        );
+    }
+    apply_curves(store, effect, index..index + 1, transform.translation);
+    state.messages.spawned(store, index);
+    sub_emitter::trigger(
+        effect,
+        store,
+        index,
+        |trigger| trigger == SubEmitterTrigger::Birth,
+        &mut state.sub_emits,
     );
+    true
+}
+
+/// creates `count` particles of a sub emitter at the parent particle
+pub(crate) fn spawn_sub_particles(
+    store: &mut ParticleStore,
+    state: &mut ParticleSpawnerState,
+    effect: &Particle2dEffect,
+    position: Vec2,
+    velocity: Vec2,
+    color: Option<LinearRgba>,
+    count: u32,
+) {
+    let transform = Transform::from_translation(position.extend(0.));
+    for _ in 0..count {
+        if !spawn_particle(store, state, effect, &transform, 0.) {
+            return;
+        }
+        let index = store.len() - 1;
+        store.velocity_x[index] += velocity.x;
+        store.velocity_y[index] += velocity.y;
+        if let Some(color) = color {
+            store.color_r[index] *= color.red;
+            store.color_g[index] *= color.green;
+            store.color_b[index] *= color.blue;
+            store.color_a[index] *= color.alpha;
+            store.start_color[index] = LinearRgba::new(
+                store.color_r[index],
+                store.color_g[index],
+                store.color_b[index],
+                store.color_a[index],
+            );
+        }
+    }
+}
+
+/// emitter time and amount of particles of every burst,
+/// that fired between `from` and `to` seconds of emitter time.
+fn burst_fires(
+    bursts: &[Burst],
+    from: f32,
+    to: f32,
+    effect: &Particle2dEffect,
+    rng: &mut ParticleRng,
+) -> Vec<(f32, u32)> {
+    let mut fires = Vec::new();
+    let mut in_window = |offset: f32, from: f32, to: f32, include_end: bool| {
+        for burst in bursts {
+            burst.fire(from, to, include_end, rng, |time, count| {
+                fires.push((offset + time, count));
+            });
+        }
+    };
+
+    match effect.duration.filter(|duration| *duration > 0.) {
+        Some(duration) if effect.looping.unwrap_or(false) => {
+            let first = (from / duration).floor() as u32;
+            let last = (to / duration).floor() as u32;
+            for cycle in first..=last {
+                let offset = cycle as f32 * duration;
+                in_window(
+                    offset,
+                    (from - offset).max(0.),
+                    (to - offset).min(duration),
+                    false,
+                );
+            }
+        }
+        // the single cycle ends this frame, bursts at its end still fire
+        Some(duration) => in_window(0., from, to.min(duration), to >= duration),
+        None => in_window(0., from, to, false),
+    }
+
+    fires
+}
+
+/// emits `spawn_amount` particles every `step` world units, spread
+/// along the path the spawner moved during the last frame.
+fn emit_along_path(
+    store: &mut ParticleStore,
+    state: &mut ParticleSpawnerState,
+    effect: &Particle2dEffect,
+    transform: &Transform,
+    travelled: Vec3,
+    step: f32,
+    delta: f32,
+) -> bool {
+    let length = travelled.length();
+    let start = transform.translation - travelled;
+    let mut emitted = false;
+
+    // distance along this frames path, until the next emission
+    let mut next = step - state.distance_travelled;
+    while next <= length {
+        let progress = next / length;
+        let mut transform = *transform;
+        transform.translation = start + travelled * progress;
+        for _ in 0..effect.spawn_amount {
+            spawn_particle(store, state, effect, &transform, delta * (1. - progress));
+        }
+        emitted = true;
+        next += step;
+    }
+
+    state.distance_travelled = length - (next - step);
+    emitted
 }
 
 fn update_particles(
@@ -317,17 +899,43 @@ fn update_particles(
     delta: f32,
     spawner_world_pos: Vec3,
     position_delta: Vec3,
+    time: f32,
 ) {
-    update_particles_simd(particles, effect, delta, spawner_world_pos, position_delta);
+    update_particles_simd(
+        particles,
+        effect,
+        delta,
+        spawner_world_pos,
+        position_delta,
+        time,
+    );
 }
 
-fn create_particle(store: &mut ParticleStore, effect: &Particle2dEffect, transform: &Transform) {
+fn create_particle(
+    store: &mut ParticleStore,
+    effect: &Particle2dEffect,
+    transform: &Transform,
+    rng: &mut ParticleRng,
+) {
+    let (offset, shape_color) = effect
+        .emission_shape
+        .sample(rng, effect.emit_from_edge.unwrap_or(false));
+
     // direction
-    let direction = effect
-        .direction
-        .as_ref()
-        .map(|m| m.rand())
-        .unwrap_or_default();
+    let direction = match effect.direction_mode {
+        None | Some(DirectionMode::Fixed) => effect
+            .direction
+            .as_ref()
+            .map(|m| m.rand_with(rng))
+            .unwrap_or_default(),
+        Some(mode) => {
+            let (base, spread) = effect
+                .direction
+                .as_ref()
+                .map_or((Vec2::ZERO, 0.), |d| (d.0, d.1));
+            Rval(mode.direction(&effect.emission_shape, offset, base), spread).rand_with(rng)
+        }
+    };
 
     // apply local rotation
     let direction = direction.rotate(transform.right().truncate());
@@ -336,87 +944,118 @@ fn create_particle(store: &mut ParticleStore, effect: &Particle2dEffect, transfo
     let speed = effect
         .linear_speed
         .as_ref()
-        .map(|s| s.rand())
+        .map(|s| s.rand_with(rng))
         .unwrap_or_default();
     // angular
     let angular = effect
         .angular_speed
         .as_ref()
-        .map(|s| s.rand())
+        .map(|s| s.rand_with(rng))
         .unwrap_or_default();
-    // angular
-    let scale = effect.scale.as_ref().map(|s| s.rand()).unwrap_or_default();
+    // scale
+    let scale = effect
+        .scale
+        .as_ref()
+        .map(|s| s.rand_with(rng))
+        .unwrap_or_default();
+    let scale_y = effect.scale_y.as_ref().map_or(scale, |s| s.rand_with(rng));
 
     let gravity_direction = effect
         .gravity_direction
         .as_ref()
-        .map(|g| g.rand())
+        .map(|g| g.rand_with(rng))
         .unwrap_or_default()
         .extend(0.);
 
     let gravity_speed = effect
         .gravity_speed
         .as_ref()
-        .map(|g| g.rand())
+        .map(|g| g.rand_with(rng))
         .unwrap_or_default();
 
     let linear_damp = effect
         .linear_damp
         .as_ref()
-        .map(|d| d.rand())
+        .map(|d| d.rand_with(rng))
         .unwrap_or_default();
 
     let angular_damp = effect
         .angular_damp
         .as_ref()
-        .map(|a| a.rand())
+        .map(|a| a.rand_with(rng))
         .unwrap_or_default();
 
     let angular_acceleration = effect
         .angular_acceleration
         .as_ref()
-        .map(|a| a.rand())
+        .map(|a| a.rand_with(rng))
         .unwrap_or_default();
 
     let linear_acceleration = effect
         .linear_acceleration
         .as_ref()
-        .map(|a| a.rand())
+        .map(|a| a.rand_with(rng))
         .unwrap_or_default();
 
-    let mut transform = *transform;
-    transform.scale = Vec3::splat(scale);
+    let radial_acceleration = effect
+        .radial_acceleration
+        .as_ref()
+        .map(|a| a.rand_with(rng))
+        .unwrap_or_default();
 
-    transform.translation += match effect.emission_shape {
-        EmissionShape::Point => Vec3::ZERO,
-        EmissionShape::Circle(radius) => {
-            Vec3::new(rand::random::<f32>() - 0.5, rand::random::<f32>() - 0.5, 0.)
-                .normalize_or_zero()
-                * radius
-                * rand::random::<f32>()
+    let tangential_acceleration = effect
+        .tangential_acceleration
+        .as_ref()
+        .map(|a| a.rand_with(rng))
+        .unwrap_or_default();
+
+    let color = shape_color
+        .or_else(|| effect.random_color.as_ref().map(|c| c.rand_with(rng)))
+        .or(effect.color)
+        .unwrap_or(LinearRgba::WHITE);
+
+    let velocity = direction * speed;
+    let mut transform = *transform;
+    transform.translation += transform.rotation * offset.extend(0.);
+    transform.scale = Vec3::new(scale, scale_y, scale);
+    if let Some(rotation) = effect.rotation.as_ref() {
+        transform.rotate_z(rotation.rand_with(rng));
+    }
+    if let Some(RotationMode::AlignToVelocity { offset }) = effect.rotation_mode {
+        if velocity != Vec2::ZERO {
+            transform.rotation = Quat::from_rotation_z(velocity.to_angle() + offset);
         }
-    };
+    }
 
     store.push(
         transform,
-        effect.lifetime.rand(),
-        (direction * speed).extend(0.),
+        effect.lifetime.rand_with(rng),
+        velocity.extend(0.),
         angular,
-        effect.color.unwrap_or(LinearRgba::WHITE),
+        color,
         linear_acceleration,
         linear_damp,
         angular_acceleration,
         angular_damp,
         gravity_speed,
         gravity_direction,
+        radial_acceleration,
+        tangential_acceleration,
     );
+
+    if let Some(animation) = effect.animation.as_ref() {
+        if animation.random_start.unwrap_or(false) {
+            let index = store.len() - 1;
+            store.frame_start[index] = rng.random_range(0..animation.frames.max(1));
+        }
+    }
 }
 
-fn load8(values: &[f32], index: usize) -> f32x8 {
+pub(crate) fn load8(values: &[f32], index: usize) -> f32x8 {
     f32x8::new(values[index..index + 8].try_into().unwrap())
 }
 
-fn store8(values: &mut [f32], index: usize, value: f32x8) {
+pub(crate) fn store8(values: &mut [f32], index: usize, value: f32x8) {
     values[index..index + 8].copy_from_slice(&value.to_array());
 }
 
@@ -426,19 +1065,35 @@ fn update_particles_simd(
     delta: f32,
     spawner_world_pos: Vec3,
     position_delta: Vec3,
+    time: f32,
 ) {
     let delta8 = f32x8::splat(delta);
     let zero = f32x8::ZERO;
     let one = f32x8::ONE;
     let simd_len = particles.len() / 8 * 8;
+    let knots = |curve: &Option<MultiCurve<f32>>| curve.as_ref().map(CurveKnots::new);
+    let speed_curve = knots(&effect.speed_curve);
+    let max_speed_curve = knots(&effect.max_speed_curve);
+    let angular_speed_curve = knots(&effect.angular_speed_curve);
+    let damping_curve = knots(&effect.damping_curve);
 
     for index in (0..simd_len).step_by(8) {
         let mut px = load8(&particles.position_x, index) + f32x8::splat(position_delta.x);
         let mut py = load8(&particles.position_y, index) + f32x8::splat(position_delta.y);
         let mut pz = load8(&particles.position_z, index) + f32x8::splat(position_delta.z);
-        let progress =
+        let mut progress =
             load8(&particles.duration_fraction, index) + delta8 / load8(&particles.duration, index);
 
+        // sampled before attractors can end the lifetime
+        let speed_curve = speed_curve.as_ref().map(|curve| curve.sample8(progress));
+        let max_speed_curve = max_speed_curve
+            .as_ref()
+            .map(|curve| curve.sample8(progress));
+        let angular_speed_curve = angular_speed_curve
+            .as_ref()
+            .map(|curve| curve.sample8(progress));
+        let damping_curve = damping_curve.as_ref().map(|curve| curve.sample8(progress));
+
         let linear_factor = one
             + progress
                 * (load8(&particles.linear_acceleration, index)
@@ -455,6 +1110,27 @@ fn update_particles_simd(
                 * delta8;
         let angular_velocity = load8(&particles.angular_velocity, index) * angular_factor;
 
+        if let Some(damping) = damping_curve {
+            let drag = (one - damping * delta8).max(zero);
+            vx *= drag;
+            vy *= drag;
+            vz *= drag;
+        }
+
+        let radial = load8(&particles.radial_acceleration, index);
+        let tangential = load8(&particles.tangential_acceleration, index);
+        if (radial.abs() + tangential.abs()).reduce_add() > 0. {
+            let dx = px - f32x8::splat(spawner_world_pos.x);
+            let dy = py - f32x8::splat(spawner_world_pos.y);
+            let distance = (dx * dx + dy * dy).sqrt();
+            let non_zero = distance.simd_gt(zero);
+            let safe_distance = non_zero.select(distance, one);
+            let nx = non_zero.select(dx / safe_distance, zero);
+            let ny = non_zero.select(dy / safe_distance, zero);
+            vx += (nx * radial - ny * tangential) * delta8;
+            vy += (ny * radial + nx * tangential) * delta8;
+        }
+
         if let Some(attractors) = &effect.attractors {
             for attractor in attractors {
                 let attractor_position = spawner_world_pos + attractor.position.extend(0.0);
@@ -462,26 +1138,56 @@ fn update_particles_simd(
                 let dy = f32x8::splat(attractor_position.y) - py;
                 let dz = f32x8::splat(attractor_position.z) - pz;
                 let distance_squared = dx * dx + dy * dy + dz * dz;
-                let non_zero = distance_squared.simd_gt(zero);
-                let safe_distance_squared = non_zero.blend(distance_squared, one);
-                let force = f32x8::splat(attractor.strength)
-                    / safe_distance_squared.max(f32x8::splat(
-                        attractor.min_distance * attractor.min_distance,
-                    ))
-                    * delta8
-                    / safe_distance_squared.sqrt();
-                let force = non_zero.blend(force, zero);
+                let mut affected = distance_squared.simd_gt(zero);
+                if let Some(radius) = attractor.radius {
+                    affected &= distance_squared.simd_le(f32x8::splat(radius * radius));
+                }
+                if let Some(kill_radius) = attractor.kill_radius {
+                    let arrived = distance_squared.simd_le(f32x8::splat(kill_radius * kill_radius));
+                    progress = arrived.select(one, progress);
+                }
+                let safe_distance_squared = affected.select(distance_squared, one);
+                let force =
+                    attractor.force8(safe_distance_squared) * delta8 / safe_distance_squared.sqrt();
+                let force = affected.select(force, zero);
                 vx += dx * force;
                 vy += dy * force;
                 vz += dz * force;
             }
         }
 
+        if let Some(turbulence) = &effect.turbulence {
+            let (fx, fy) = turbulence.force8(px, py, time);
+            vx += fx * delta8;
+            vy += fy * delta8;
+        }
+
+        if let Some(max_speed) = max_speed_curve {
+            let speed = (vx * vx + vy * vy + vz * vz).sqrt();
+            let limit = speed.simd_gt(max_speed).select(max_speed / speed, one);
+            vx *= limit;
+            vy *= limit;
+            vz *= limit;
+        }
+
+        let step = match speed_curve {
+            Some(speed) => speed * delta8,
+            None => delta8,
+        };
+        let angular_step = match angular_speed_curve {
+            Some(angular_speed) => angular_speed * delta8,
+            None => delta8,
+        };
+
         let gravity = load8(&particles.gravity_speed, index) * delta8;
-        px += vx * delta8 + load8(&particles.gravity_x, index) * gravity;
-        py += vy * delta8 + load8(&particles.gravity_y, index) * gravity;
-        pz += vz * delta8 + load8(&particles.gravity_z, index) * gravity;
-        let rotation = load8(&particles.rotation, index) + angular_velocity * delta8;
+        px += vx * step + load8(&particles.gravity_x, index) * gravity;
+        py += vy * step + load8(&particles.gravity_y, index) * gravity;
+        pz += vz * step + load8(&particles.gravity_z, index) * gravity;
+        let mut rotation = load8(&particles.rotation, index) + angular_velocity * angular_step;
+        if let Some(RotationMode::AlignToVelocity { offset }) = effect.rotation_mode {
+            let moving = (vx * vx + vy * vy).simd_gt(zero);
+            rotation = moving.select(vy.atan2(vx) + f32x8::splat(offset), rotation);
+        }
 
         store8(&mut particles.position_x, index, px);
         store8(&mut particles.position_y, index, py);
@@ -502,21 +1208,71 @@ fn update_particles_simd(
             delta,
             spawner_world_pos,
             position_delta,
+            time,
         );
     }
 
+    apply_curves(particles, effect, 0..particles.len(), spawner_world_pos);
+}
+
+/// samples the scale and color curves by their input, picks the
+/// sprite frames and the velocity stretch
+fn apply_curves(
+    particles: &mut ParticleStore,
+    effect: &Particle2dEffect,
+    range: Range<usize>,
+    spawner_world_pos: Vec3,
+) {
+    let scale_input = effect.scale_curve_input.unwrap_or_default();
     if let Some(scale_curve) = effect.scale_curve.as_ref() {
-        for index in 0..particles.len() {
-            let scale = scale_curve.lerp(particles.duration_fraction[index]);
+        for index in range.clone() {
+            let position = scale_input.position(particles, index, spawner_world_pos);
+            let scale = scale_curve.lerp(position);
             particles.scale_x[index] = scale;
             particles.scale_y[index] = scale;
             particles.scale_z[index] = scale;
         }
     }
 
+    if let Some(scale_y_curve) = effect.scale_y_curve.as_ref() {
+        for index in range.clone() {
+            let position = scale_input.position(particles, index, spawner_world_pos);
+            particles.scale_y[index] = scale_y_curve.lerp(position);
+        }
+    }
+
+    if let Some(animation) = effect.animation.as_ref() {
+        for index in range.clone() {
+            particles.frame[index] = animation.frame(
+                particles.frame_start[index],
+                particles.duration_fraction[index],
+                particles.duration[index],
+            );
+        }
+    }
+
+    if let Some(stretch) = effect.stretch.as_ref() {
+        for index in range.clone() {
+            let velocity = Vec2::new(particles.velocity_x[index], particles.velocity_y[index]);
+            particles.stretch[index] = stretch.length(velocity.length());
+        }
+    }
+
     if let Some(color_curve) = effect.color_curve.as_ref() {
-        for index in 0..particles.len() {
-            let color = color_curve.lerp(particles.duration_fraction[index]);
+        let multiply = effect.color_curve_mode == Some(ColorCurveMode::Multiply);
+        let input = effect.color_curve_input.unwrap_or_default();
+        for index in range {
+            let position = input.position(particles, index, spawner_world_pos);
+            let mut color = color_curve.lerp(position);
+            if multiply {
+                let start = particles.start_color[index];
+                color = LinearRgba::new(
+                    color.red * start.red,
+                    color.green * start.green,
+                    color.blue * start.blue,
+                    color.alpha * start.alpha,
+                );
+            }
             particles.color_r[index] = color.red;
             particles.color_g[index] = color.green;
             particles.color_b[index] = color.blue;
@@ -532,6 +1288,7 @@ fn update_particle_scalar(
     delta: f32,
     spawner_world_pos: Vec3,
     position_delta: Vec3,
+    time: f32,
 ) {
     particles.position_x[index] += position_delta.x;
     particles.position_y[index] += position_delta.y;
@@ -551,6 +1308,26 @@ fn update_particle_scalar(
             * delta;
     particles.angular_velocity[index] *= angular_factor;
 
+    if let Some(damping) = &effect.damping_curve {
+        let drag = (1.0 - damping.lerp(progress) * delta).max(0.0);
+        particles.velocity_x[index] *= drag;
+        particles.velocity_y[index] *= drag;
+        particles.velocity_z[index] *= drag;
+    }
+
+    let radial = particles.radial_acceleration[index];
+    let tangential = particles.tangential_acceleration[index];
+    if radial != 0.0 || tangential != 0.0 {
+        let dx = particles.position_x[index] - spawner_world_pos.x;
+        let dy = particles.position_y[index] - spawner_world_pos.y;
+        let distance = (dx * dx + dy * dy).sqrt();
+        if distance > 0.0 {
+            let (nx, ny) = (dx / distance, dy / distance);
+            particles.velocity_x[index] += (nx * radial - ny * tangential) * delta;
+            particles.velocity_y[index] += (ny * radial + nx * tangential) * delta;
+        }
+    }
+
     if let Some(attractors) = &effect.attractors {
         for attractor in attractors {
             let attractor_position = spawner_world_pos + attractor.position.extend(0.0);
@@ -559,11 +1336,18 @@ fn update_particle_scalar(
             let dz = attractor_position.z - particles.position_z[index];
             let distance_squared = dx * dx + dy * dy + dz * dz;
 
-            if distance_squared > 0.0 {
-                let force = attractor.strength
-                    / distance_squared.max(attractor.min_distance * attractor.min_distance)
-                    * delta
-                    / distance_squared.sqrt();
+            if attractor
+                .kill_radius
+                .is_some_and(|kill_radius| distance_squared <= kill_radius * kill_radius)
+            {
+                particles.duration_fraction[index] = 1.0;
+            }
+
+            let in_radius = attractor
+                .radius
+                .is_none_or(|radius| distance_squared <= radius * radius);
+            if distance_squared > 0.0 && in_radius {
+                let force = attractor.force(distance_squared) * delta / distance_squared.sqrt();
                 particles.velocity_x[index] += dx * force;
                 particles.velocity_y[index] += dy * force;
                 particles.velocity_z[index] += dz * force;
@@ -571,15 +1355,53 @@ fn update_particle_scalar(
         }
     }
 
+    if let Some(turbulence) = &effect.turbulence {
+        let position = Vec2::new(particles.position_x[index], particles.position_y[index]);
+        let force = turbulence.force(position, time) * delta;
+        particles.velocity_x[index] += force.x;
+        particles.velocity_y[index] += force.y;
+    }
+
+    if let Some(max_speed) = &effect.max_speed_curve {
+        let velocity = Vec3::new(
+            particles.velocity_x[index],
+            particles.velocity_y[index],
+            particles.velocity_z[index],
+        );
+        let speed = velocity.length();
+        let max_speed = max_speed.lerp(progress);
+        if speed > max_speed {
+            let limit = max_speed / speed;
+            particles.velocity_x[index] *= limit;
+            particles.velocity_y[index] *= limit;
+            particles.velocity_z[index] *= limit;
+        }
+    }
+
+    let step = effect
+        .speed_curve
+        .as_ref()
+        .map_or(delta, |speed| speed.lerp(progress) * delta);
+    let angular_step = effect
+        .angular_speed_curve
+        .as_ref()
+        .map_or(delta, |angular_speed| angular_speed.lerp(progress) * delta);
+
     let gravity = particles.gravity_speed[index] * delta;
     particles.position_x[index] +=
-        particles.velocity_x[index] * delta + particles.gravity_x[index] * gravity;
+        particles.velocity_x[index] * step + particles.gravity_x[index] * gravity;
     particles.position_y[index] +=
-        particles.velocity_y[index] * delta + particles.gravity_y[index] * gravity;
+        particles.velocity_y[index] * step + particles.gravity_y[index] * gravity;
     particles.position_z[index] +=
-        particles.velocity_z[index] * delta + particles.gravity_z[index] * gravity;
+        particles.velocity_z[index] * step + particles.gravity_z[index] * gravity;
 
-    particles.rotation[index] += particles.angular_velocity[index] * delta;
+    particles.rotation[index] += particles.angular_velocity[index] * angular_step;
+    if let Some(RotationMode::AlignToVelocity { offset }) = effect.rotation_mode {
+        let (vx, vy) = (particles.velocity_x[index], particles.velocity_y[index]);
+        if vx * vx + vy * vy > 0. {
+            particles.rotation[index] = vy.atan2(vx) + offset;
+        }
+    }
 }
 
 pub(crate) fn calculate_particle_bounds(
@@ -613,6 +1435,14 @@ pub(crate) fn calculate_particle_bounds(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        collision::{ColliderShape, CollisionResponse, WorldCollider},
+        messages::ParticleMessages,
+        sub_emitter::SubEmitter,
+        values::{ColorDist, Rdist},
+        AnimationMode, CurveInput, SpriteAnimation, VelocityStretch,
+    };
+    use bevy_math::curve::EaseFunction;
     use std::{
         hint::black_box,
         time::{Duration, Instant},
@@ -633,6 +1463,8 @@ mod tests {
                 0.1,
                 9.81,
                 Vec3::NEG_Y,
+                0.0,
+                0.0,
             );
         }
         particles
@@ -645,9 +1477,17 @@ mod tests {
         let mut scalar = simd.clone();
         let delta = 1.0 / 60.0;
 
-        update_particles(&mut simd, &effect, delta, Vec3::ZERO, Vec3::ZERO);
+        update_particles(&mut simd, &effect, delta, Vec3::ZERO, Vec3::ZERO, 0.);
         for index in 0..scalar.len() {
-            update_particle_scalar(&mut scalar, &effect, index, delta, Vec3::ZERO, Vec3::ZERO);
+            update_particle_scalar(
+                &mut scalar,
+                &effect,
+                index,
+                delta,
+                Vec3::ZERO,
+                Vec3::ZERO,
+                0.,
+            );
         }
 
         for index in 0..simd.len() {
@@ -667,6 +1507,1087 @@ mod tests {
         }
     }
 
+    #[test]
+    fn curve_knots_match_scalar_curve() {
+        let curve = MultiCurve::new()
+            .with_point(2., 0.1, None)
+            .with_point(-1., 0.4, None)
+            .with_point(5., 0.4, None)
+            .with_point(3., 0.9, Some(EaseFunction::QuadraticInOut));
+        let knots = CurveKnots::new(&curve);
+
+        for chunk in 0..16 {
+            let positions = std::array::from_fn(|lane| (chunk * 8 + lane) as f32 / 100. - 0.1);
+            let simd = knots.sample8(f32x8::new(positions)).to_array();
+            for (position, actual) in positions.into_iter().zip(simd) {
+                let expected = curve.lerp(position);
+                // eased segments are flattened into linear steps
+                let tolerance = if position > 0.4 && position < 0.9 {
+                    1e-2
+                } else {
+                    1e-5
+                };
+                assert!(
+                    (actual - expected).abs() < tolerance,
+                    "{position}: {actual} != {expected}"
+                );
+            }
+        }
+
+        let empty = CurveKnots::new(&MultiCurve::new());
+        assert_eq!(empty.sample8(f32x8::splat(0.5)), f32x8::ZERO);
+    }
+
+    #[test]
+    fn lifetime_curves_match_scalar_update() {
+        let curve = |start: f32, end: f32| {
+            Some(
+                MultiCurve::new()
+                    .with_point(start, 0., None)
+                    .with_point(end, 1., None),
+            )
+        };
+        let effect = Particle2dEffect {
+            speed_curve: curve(2., 0.5),
+            max_speed_curve: curve(9., 4.),
+            angular_speed_curve: curve(-1., 3.),
+            damping_curve: curve(0.5, 3.),
+            ..Default::default()
+        };
+        let mut simd = particle_store(16);
+        for index in 0..simd.len() {
+            simd.duration[index] = 1.;
+            simd.duration_fraction[index] = index as f32 / 16.;
+        }
+        let mut scalar = simd.clone();
+        let delta = 1.0 / 60.0;
+
+        update_particles(&mut simd, &effect, delta, Vec3::ZERO, Vec3::ZERO, 0.);
+        for index in 0..scalar.len() {
+            update_particle_scalar(
+                &mut scalar,
+                &effect,
+                index,
+                delta,
+                Vec3::ZERO,
+                Vec3::ZERO,
+                0.,
+            );
+        }
+
+        for index in 0..simd.len() {
+            for (actual, expected) in [
+                (simd.position_x[index], scalar.position_x[index]),
+                (simd.position_y[index], scalar.position_y[index]),
+                (simd.velocity_x[index], scalar.velocity_x[index]),
+                (simd.velocity_y[index], scalar.velocity_y[index]),
+                (simd.rotation[index], scalar.rotation[index]),
+            ] {
+                assert!((actual - expected).abs() < 1e-5, "{actual} != {expected}");
+            }
+        }
+
+        // the speed limit lowers with the lifetime
+        let speed = |index: usize| Vec2::new(scalar.velocity_x[index], scalar.velocity_y[index]);
+        assert!(speed(0).length() <= 9.);
+        assert!(speed(15).length() <= 4.5);
+    }
+
+    #[test]
+    fn radial_and_tangential_acceleration_orbit_the_spawner() {
+        let effect = Particle2dEffect::default();
+        let mut simd = particle_store(9);
+        for index in 0..simd.len() {
+            let angle = index as f32;
+            simd.position_x[index] = 5. + angle.cos() * 20.;
+            simd.position_y[index] = angle.sin() * 20.;
+            simd.radial_acceleration[index] = -30.;
+            simd.tangential_acceleration[index] = 50.;
+        }
+        simd.position_x[8] = 5.;
+        simd.position_y[8] = 0.;
+        let mut scalar = simd.clone();
+        let delta = 1.0 / 60.0;
+        let spawner = Vec3::new(5., 0., 0.);
+
+        update_particles(&mut simd, &effect, delta, spawner, Vec3::ZERO, 0.);
+        for index in 0..scalar.len() {
+            update_particle_scalar(&mut scalar, &effect, index, delta, spawner, Vec3::ZERO, 0.);
+        }
+
+        for index in 0..simd.len() {
+            for (actual, expected) in [
+                (simd.position_x[index], scalar.position_x[index]),
+                (simd.position_y[index], scalar.position_y[index]),
+                (simd.velocity_x[index], scalar.velocity_x[index]),
+                (simd.velocity_y[index], scalar.velocity_y[index]),
+            ] {
+                assert!((actual - expected).abs() < 1e-4, "{actual} != {expected}");
+            }
+        }
+
+        // at angle 0, the tangent points up and the pull to the left
+        let mut store = particle_store(1);
+        store.position_x[0] = 25.;
+        store.radial_acceleration[0] = -30.;
+        store.tangential_acceleration[0] = 50.;
+        let before = Vec2::new(store.velocity_x[0], store.velocity_y[0]);
+        update_particle_scalar(&mut store, &effect, 0, delta, spawner, Vec3::ZERO, 0.);
+        let change = Vec2::new(store.velocity_x[0], store.velocity_y[0]) - before;
+        assert!(change.x < 0. && change.y > 0., "{change}");
+    }
+
+    #[test]
+    fn turbulence_matches_scalar_update() {
+        let effect = Particle2dEffect {
+            turbulence: Some(crate::noise::Turbulence {
+                octaves: Some(2),
+                scroll: Some(Vec2::new(3., 1.)),
+                ..crate::noise::Turbulence::new(200., 0.1)
+            }),
+            ..Default::default()
+        };
+        let mut simd = particle_store(8);
+        for index in 0..simd.len() {
+            simd.position_x[index] = index as f32 * 7.3;
+            simd.position_y[index] = index as f32 * -3.1;
+        }
+        let mut scalar = simd.clone();
+        let delta = 1.0 / 60.0;
+
+        update_particles(&mut simd, &effect, delta, Vec3::ZERO, Vec3::ZERO, 2.5);
+        for index in 0..scalar.len() {
+            update_particle_scalar(
+                &mut scalar,
+                &effect,
+                index,
+                delta,
+                Vec3::ZERO,
+                Vec3::ZERO,
+                2.5,
+            );
+        }
+
+        let mut plain = particle_store(8);
+        let plain_effect = Particle2dEffect::default();
+        update_particles(
+            &mut plain,
+            &plain_effect,
+            delta,
+            Vec3::ZERO,
+            Vec3::ZERO,
+            2.5,
+        );
+        for index in 0..simd.len() {
+            assert_eq!(simd.velocity_x[index], scalar.velocity_x[index]);
+            assert_eq!(simd.velocity_y[index], scalar.velocity_y[index]);
+        }
+        assert_ne!(scalar.velocity_x[3], plain.velocity_x[3]);
+    }
+
+    #[test]
+    fn attractor_falloffs_match_scalar_update() {
+        use crate::{Attractor, Falloff};
+
+        for falloff in [
+            Falloff::Constant,
+            Falloff::Linear,
+            Falloff::Inverse,
+            Falloff::InverseSquare,
+            Falloff::Smoothstep,
+        ] {
+            let effect = Particle2dEffect {
+                attractors: Some(vec![Attractor {
+                    falloff: Some(falloff),
+                    radius: Some(60.),
+                    kill_radius: Some(5.),
+                    ..Attractor::new(Vec2::ZERO, -500., 2.)
+                }]),
+                ..Default::default()
+            };
+            let mut simd = particle_store(8);
+            for index in 0..simd.len() {
+                simd.position_x[index] = index as f32 * 10. + 1.;
+                simd.position_y[index] = 2.;
+            }
+            let mut scalar = simd.clone();
+            let delta = 1.0 / 60.0;
+
+            update_particles(&mut simd, &effect, delta, Vec3::ZERO, Vec3::ZERO, 0.);
+            for index in 0..scalar.len() {
+                update_particle_scalar(
+                    &mut scalar,
+                    &effect,
+                    index,
+                    delta,
+                    Vec3::ZERO,
+                    Vec3::ZERO,
+                    0.,
+                );
+            }
+
+            for index in 0..simd.len() {
+                for (actual, expected) in [
+                    (simd.velocity_x[index], scalar.velocity_x[index]),
+                    (simd.velocity_y[index], scalar.velocity_y[index]),
+                    (
+                        simd.duration_fraction[index],
+                        scalar.duration_fraction[index],
+                    ),
+                ] {
+                    assert!(
+                        (actual - expected).abs() < 1e-4,
+                        "{falloff:?} {actual} != {expected}"
+                    );
+                }
+            }
+
+            // arrived particles die, repulsors push away, far ones are ignored
+            assert_eq!(scalar.duration_fraction[0], 1.);
+            let plain = Particle2dEffect::default();
+            let mut reference = particle_store(8);
+            update_particle_scalar(&mut reference, &plain, 7, delta, Vec3::ZERO, Vec3::ZERO, 0.);
+            assert_eq!(scalar.velocity_x[7], reference.velocity_x[7]);
+            assert!(
+                scalar.velocity_x[2] > reference.velocity_x[2],
+                "{falloff:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn aligned_rotation_matches_scalar_update() {
+        let effect = Particle2dEffect {
+            rotation_mode: Some(RotationMode::AlignToVelocity { offset: 0.5 }),
+            ..Default::default()
+        };
+        let mut simd = particle_store(8);
+        for index in 0..8 {
+            let angle = index as f32 * 0.8 - 3.;
+            simd.velocity_x[index] = angle.cos() * 10.;
+            simd.velocity_y[index] = angle.sin() * 10.;
+        }
+        simd.velocity_x[7] = 0.;
+        simd.velocity_y[7] = 0.;
+        let mut scalar = simd.clone();
+        let delta = 1.0 / 60.0;
+
+        update_particles(&mut simd, &effect, delta, Vec3::ZERO, Vec3::ZERO, 0.);
+        for index in 0..scalar.len() {
+            update_particle_scalar(
+                &mut scalar,
+                &effect,
+                index,
+                delta,
+                Vec3::ZERO,
+                Vec3::ZERO,
+                0.,
+            );
+        }
+
+        for index in 0..simd.len() {
+            let (actual, expected) = (simd.rotation[index], scalar.rotation[index]);
+            assert!((actual - expected).abs() < 1e-4, "{actual} != {expected}");
+        }
+        // falls back to the free rotation without velocity
+        assert!((scalar.rotation[7] - 0.5 * delta).abs() < 1e-6);
+
+        let velocity = Vec2::new(scalar.velocity_x[0], scalar.velocity_y[0]);
+        assert!((scalar.rotation[0] - velocity.to_angle() - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn scale_axes_and_stretch_are_independent() {
+        let effect = Particle2dEffect {
+            scale: Some(Rdist::new(2., 0.)),
+            scale_y: Some(Rdist::new(4., 0.)),
+            linear_speed: Some(Rdist::new(100., 0.)),
+            direction: Some(Rval::new(Vec2::Y, 0.)),
+            stretch: Some(VelocityStretch {
+                factor: 0.05,
+                max: Some(3.),
+            }),
+            ..Default::default()
+        };
+        let mut store = ParticleStore::default();
+        spawn_particle(
+            &mut store,
+            &mut ParticleSpawnerState::default(),
+            &effect,
+            &Transform::default(),
+            0.,
+        );
+        assert_eq!((store.scale_x[0], store.scale_y[0]), (2., 4.));
+        assert_eq!(store.stretch[0], 3.);
+
+        let effect = Particle2dEffect {
+            scale_curve: Some(MultiCurve::new().with_point(1., 0., None)),
+            scale_y_curve: Some(MultiCurve::new().with_point(0.5, 0., None)),
+            stretch: None,
+            ..effect
+        };
+        apply_curves(&mut store, &effect, 0..1, Vec3::ZERO);
+        assert_eq!((store.scale_x[0], store.scale_y[0]), (1., 0.5));
+    }
+
+    #[test]
+    fn stretch_keeps_the_rotation_offset() {
+        let stretch = Some(VelocityStretch {
+            factor: 0.05,
+            max: None,
+        });
+        let aligned = Particle2dEffect {
+            rotation_mode: Some(RotationMode::AlignToVelocity { offset: 0.5 }),
+            stretch,
+            ..Default::default()
+        };
+        let free = Particle2dEffect {
+            rotation: Some(Rdist::new(1., 0.)),
+            angular_speed: Some(Rdist::new(2., 0.)),
+            stretch,
+            ..Default::default()
+        };
+        let delta = 1.0 / 60.0;
+
+        for effect in [aligned, free] {
+            let mut store = particle_store(8);
+            for index in 0..8 {
+                store.velocity_x[index] = 30.;
+                store.velocity_y[index] = 40.;
+                store.rotation[index] = 1.;
+                store.angular_velocity[index] = 2.;
+                store.angular_acceleration[index] = 0.;
+                store.angular_damp[index] = 0.;
+            }
+            update_particles(&mut store, &effect, delta, Vec3::ZERO, Vec3::ZERO, 0.);
+
+            let velocity = Vec2::new(store.velocity_x[0], store.velocity_y[0]);
+            let expected = match effect.rotation_mode {
+                Some(RotationMode::AlignToVelocity { offset }) => velocity.to_angle() + offset,
+                _ => 1. + 2. * delta,
+            };
+            assert!((store.rotation[0] - expected).abs() < 1e-5);
+            assert!((store.stretch[0] - (1. + velocity.length() * 0.05)).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn curves_sample_speed_and_distance() {
+        let effect = Particle2dEffect {
+            scale_curve: Some(
+                MultiCurve::new()
+                    .with_point(0., 0., None)
+                    .with_point(10., 1., None),
+            ),
+            scale_curve_input: Some(CurveInput::Distance { min: 0., max: 100. }),
+            color_curve: Some(
+                MultiCurve::new()
+                    .with_point(LinearRgba::BLACK, 0., None)
+                    .with_point(LinearRgba::WHITE, 1., None),
+            ),
+            color_curve_input: Some(CurveInput::Speed { min: 10., max: 30. }),
+            ..Default::default()
+        };
+        let mut store = particle_store(3);
+        for (index, speed) in [0., 20., 50.].into_iter().enumerate() {
+            store.velocity_x[index] = speed;
+            store.velocity_y[index] = 0.;
+            store.position_x[index] = 10. + speed;
+        }
+
+        apply_curves(&mut store, &effect, 0..3, Vec3::new(10., 0., 0.));
+        assert_eq!(store.color_r, [0., 0.5, 1.]);
+        assert_eq!(store.scale_x, [0., 2., 5.]);
+    }
+
+    #[test]
+    fn sprite_animations_pick_frames() {
+        let animation = |mode| SpriteAnimation {
+            mode: Some(mode),
+            first_frame: Some(4),
+            ..SpriteAnimation::new(4)
+        };
+        let frames = |animation: SpriteAnimation, start: u32| {
+            (0..8)
+                .map(|step| animation.frame(start, step as f32 / 8., 8.))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            frames(animation(AnimationMode::Lifetime), 0),
+            [4, 4, 5, 5, 6, 6, 7, 7]
+        );
+        assert_eq!(frames(animation(AnimationMode::Fixed), 2), [6; 8]);
+        assert_eq!(
+            frames(animation(AnimationMode::Loop { fps: 1. }), 1),
+            [5, 6, 7, 4, 5, 6, 7, 4]
+        );
+        assert_eq!(
+            frames(animation(AnimationMode::PingPong { fps: 1. }), 0),
+            [4, 5, 6, 7, 6, 5, 4, 5]
+        );
+
+        // random start frames stay inside the range
+        let effect = Particle2dEffect {
+            spawn_amount: 100,
+            animation: Some(SpriteAnimation {
+                random_start: Some(true),
+                ..animation(AnimationMode::Fixed)
+            }),
+            ..Default::default()
+        };
+        let mut store = ParticleStore::default();
+        tick_spawner(
+            &mut store,
+            &mut ParticleSpawnerState::default(),
+            &effect,
+            &Transform::default(),
+            Duration::from_millis(100),
+            false,
+            Colliders::default(),
+        );
+        assert!(store.frame.iter().all(|frame| (4..8).contains(frame)));
+        assert!(store.frame.iter().any(|frame| *frame != store.frame[0]));
+    }
+
+    #[test]
+    fn distance_emission_fills_the_path() {
+        let effect = Particle2dEffect {
+            spawn_amount: 1,
+            spawn_distance: Some(100.),
+            ..Default::default()
+        };
+        let mut state = ParticleSpawnerState::default();
+        let mut store = ParticleStore::default();
+
+        let transform = Transform::from_xyz(800., 0., 0.);
+        emit_along_path(
+            &mut store,
+            &mut state,
+            &effect,
+            &transform,
+            Vec3::X * 850.,
+            100.,
+            0.,
+        );
+        assert_eq!(store.len(), 8);
+        assert!((store.position_x[0] - 50.).abs() < 1e-3);
+        assert!((state.distance_travelled - 50.).abs() < 1e-3);
+
+        let transform = Transform::from_xyz(840., 0., 0.);
+        emit_along_path(
+            &mut store,
+            &mut state,
+            &effect,
+            &transform,
+            Vec3::X * 40.,
+            100.,
+            0.,
+        );
+        assert_eq!(store.len(), 8);
+        assert!((state.distance_travelled - 90.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn bursts_follow_the_emitter_lifecycle() {
+        let effect = Particle2dEffect {
+            spawn_amount: 0,
+            lifetime: Rdist::new(100., 0.),
+            duration: Some(1.),
+            start_delay: Some(0.52),
+            bursts: Some(vec![
+                Burst::new(0., 10),
+                Burst {
+                    cycles: Some(3),
+                    interval: Some(0.1),
+                    ..Burst::new(0.2, 1)
+                },
+            ]),
+            ..Default::default()
+        };
+        let mut state = ParticleSpawnerState::default();
+        let mut store = ParticleStore::default();
+        let frame = |store: &mut ParticleStore, state: &mut ParticleSpawnerState| {
+            tick_spawner(
+                store,
+                state,
+                &effect,
+                &Transform::default(),
+                Duration::from_millis(50),
+                false,
+                Colliders::default(),
+            );
+        };
+
+        for _ in 0..10 {
+            frame(&mut store, &mut state);
+        }
+        assert_eq!(store.len(), 0, "nothing during the start delay");
+
+        frame(&mut store, &mut state);
+        assert_eq!(store.len(), 10);
+
+        for _ in 0..20 {
+            frame(&mut store, &mut state);
+        }
+        assert_eq!(store.len(), 13);
+        assert!(!state.active);
+    }
+
+    #[test]
+    fn bursts_fire_at_the_end_and_looping_time_wraps() {
+        let mut effect = Particle2dEffect {
+            spawn_amount: 0,
+            lifetime: Rdist::new(100., 0.),
+            duration: Some(0.5),
+            bursts: Some(vec![Burst::new(0.5, 1)]),
+            ..Default::default()
+        };
+        let mut state = ParticleSpawnerState::default();
+        let mut store = ParticleStore::default();
+        let frame = |effect: &Particle2dEffect,
+                     store: &mut ParticleStore,
+                     state: &mut ParticleSpawnerState| {
+            tick_spawner(
+                store,
+                state,
+                effect,
+                &Transform::default(),
+                Duration::from_millis(250),
+                false,
+                Colliders::default(),
+            );
+        };
+
+        frame(&effect, &mut store, &mut state);
+        frame(&effect, &mut store, &mut state);
+        assert_eq!(store.len(), 1, "burst at the end of the duration");
+        assert!(!state.active);
+
+        effect.looping = Some(true);
+        effect.bursts = Some(vec![Burst::new(0.1, 1)]);
+        let mut state = ParticleSpawnerState::default();
+        let mut store = ParticleStore::default();
+        for _ in 0..10 {
+            frame(&effect, &mut store, &mut state);
+        }
+        assert_eq!(store.len(), 5, "one burst per cycle");
+        assert!(state.elapsed < 0.5);
+    }
+
+    #[test]
+    fn low_frame_rates_keep_the_emission_density() {
+        let effect = Particle2dEffect {
+            spawn_rate: 0.01,
+            spawn_amount: 2,
+            lifetime: Rdist::new(100., 0.),
+            linear_speed: Some(Rdist::new(100., 0.)),
+            direction: Some(Rval::new(Vec2::X, 0.)),
+            ..Default::default()
+        };
+        let mut state = ParticleSpawnerState::default();
+        let mut store = ParticleStore::default();
+        let delta = Duration::from_millis(105);
+        tick_spawner(
+            &mut store,
+            &mut state,
+            &effect,
+            &Transform::default(),
+            delta,
+            false,
+            Colliders::default(),
+        );
+        assert_eq!(store.len(), 20);
+
+        // sub-stepped births spread the particles along their path
+        let mut positions = store.position_x.clone();
+        positions.sort_by(f32::total_cmp);
+        positions.dedup_by(|a, b| (*a - *b).abs() < 1e-3);
+        assert_eq!(positions.len(), 10);
+        assert!((positions[0] - 0.5).abs() < 1e-3);
+
+        let effect = Particle2dEffect {
+            spawn_per_second: Some(25.),
+            ..effect
+        };
+        let mut state = ParticleSpawnerState::default();
+        let mut store = ParticleStore::default();
+        for _ in 0..10 {
+            let delta = Duration::from_millis(100);
+            tick_spawner(
+                &mut store,
+                &mut state,
+                &effect,
+                &Transform::default(),
+                delta,
+                false,
+                Colliders::default(),
+            );
+        }
+        assert_eq!(store.len(), 25);
+    }
+
+    #[test]
+    fn full_spawners_keep_simulating_and_recycle() {
+        let effect = Particle2dEffect {
+            spawn_rate: 0.1,
+            spawn_amount: 2,
+            lifetime: Rdist::new(10., 0.),
+            ..Default::default()
+        };
+        let delta = Duration::from_millis(100);
+
+        let mut state = ParticleSpawnerState::with_capacity(3, OverflowPolicy::Skip);
+        let mut store = ParticleStore::default();
+        for _ in 0..3 {
+            tick_spawner(
+                &mut store,
+                &mut state,
+                &effect,
+                &Transform::default(),
+                delta,
+                false,
+                Colliders::default(),
+            );
+        }
+        assert_eq!(store.len(), 3);
+        assert!(store.capacity() >= 3);
+        assert!(store
+            .duration_fraction
+            .iter()
+            .all(|fraction| *fraction > 0.));
+
+        let fractions = store.duration_fraction.clone();
+        tick_spawner(
+            &mut store,
+            &mut state,
+            &effect,
+            &Transform::default(),
+            delta,
+            false,
+            Colliders::default(),
+        );
+        assert!(store
+            .duration_fraction
+            .iter()
+            .zip(fractions)
+            .all(|(after, before)| *after > before));
+
+        let mut state = ParticleSpawnerState::with_capacity(3, OverflowPolicy::RecycleOldest);
+        let mut store = ParticleStore::default();
+        for _ in 0..3 {
+            tick_spawner(
+                &mut store,
+                &mut state,
+                &effect,
+                &Transform::default(),
+                delta,
+                false,
+                Colliders::default(),
+            );
+        }
+        assert_eq!(store.len(), 3);
+        // only the two particles of the last frame and one of the frame before remain
+        let oldest = store.duration_fraction.iter().copied().fold(0., f32::max);
+        assert!((oldest - 0.01).abs() < 1e-4, "{oldest}");
+    }
+
+    #[test]
+    fn seeded_spawners_are_reproducible() {
+        let effect = Particle2dEffect {
+            spawn_rate: 0.05,
+            spawn_amount: 3,
+            lifetime: Rdist::new(2., 0.5),
+            linear_speed: Some(Rdist::new(100., 0.5)),
+            direction: Some(Rval::new(Vec2::X, 0.3)),
+            emission_shape: EmissionShape::Circle(20.),
+            bursts: Some(vec![Burst {
+                probability: Some(0.5),
+                cycles: Some(10),
+                interval: Some(0.1),
+                ..Burst::new(0., 5)
+            }]),
+            ..Default::default()
+        };
+        let run = |seed: u64| {
+            let mut state = ParticleSpawnerState {
+                rng: ParticleRng::new(seed),
+                ..Default::default()
+            };
+            let mut store = ParticleStore::default();
+            for frame in 0..30 {
+                tick_spawner(
+                    &mut store,
+                    &mut state,
+                    &effect,
+                    &Transform::from_xyz(frame as f32, 0., 0.),
+                    Duration::from_millis(16 + frame % 3),
+                    false,
+                    Colliders::default(),
+                );
+            }
+            store
+        };
+
+        assert!(run(7) == run(7));
+        assert!(run(7) != run(8));
+    }
+
+    #[test]
+    fn birth_colors_are_tinted_by_the_curve() {
+        let mut effect = Particle2dEffect {
+            spawn_amount: 50,
+            random_color: Some(ColorDist::Palette(vec![LinearRgba::RED, LinearRgba::BLUE])),
+            color_curve: Some(
+                MultiCurve::new()
+                    .with_point(LinearRgba::WHITE, 0., None)
+                    .with_point(LinearRgba::rgb(0.5, 0.5, 0.5), 1., None),
+            ),
+            color_curve_mode: Some(ColorCurveMode::Multiply),
+            ..Default::default()
+        };
+        let mut state = ParticleSpawnerState::default();
+        let mut store = ParticleStore::default();
+        tick_spawner(
+            &mut store,
+            &mut state,
+            &effect,
+            &Transform::default(),
+            Duration::from_millis(100),
+            false,
+            Colliders::default(),
+        );
+        assert!(store.start_color.contains(&LinearRgba::RED));
+        assert!(store.start_color.contains(&LinearRgba::BLUE));
+
+        let all = 0..store.len();
+        store.duration_fraction.fill(1.);
+        apply_curves(&mut store, &effect, all.clone(), Vec3::ZERO);
+        for index in 0..store.len() {
+            let color = store.start_color[index];
+            assert_eq!(store.color_r[index], color.red * 0.5);
+            assert_eq!(store.color_b[index], color.blue * 0.5);
+        }
+
+        // the curve overwrites the birth color by default
+        effect.color_curve_mode = None;
+        apply_curves(&mut store, &effect, all, Vec3::ZERO);
+        assert!(store.color_r.iter().all(|red| *red == 0.5));
+    }
+
+    #[test]
+    fn particles_stop_at_colliders() {
+        let effect = Particle2dEffect {
+            lifetime: Rdist::new(10., 0.),
+            linear_speed: Some(Rdist::new(200., 0.)),
+            direction: Some(Rval(Vec2::NEG_Y, 0.)),
+            collision: Some(CollisionResponse::Stick),
+            ..Default::default()
+        };
+        let ground = [WorldCollider::new(
+            ColliderShape::HalfPlane { normal: Vec2::Y },
+            Vec2::new(0., -50.),
+            0.,
+        )];
+        let mut state = ParticleSpawnerState::default();
+        let mut store = ParticleStore::default();
+        for _ in 0..60 {
+            tick_spawner(
+                &mut store,
+                &mut state,
+                &effect,
+                &Transform::default(),
+                Duration::from_millis(16),
+                false,
+                Colliders {
+                    shapes: &ground,
+                    ..Default::default()
+                },
+            );
+        }
+
+        assert!(store.position_y.iter().all(|y| *y >= -50.));
+        assert!(store.position_y.iter().filter(|y| **y == -50.).count() > 5);
+    }
+
+    #[test]
+    fn relative_spawners_carry_particles_past_colliders() {
+        let effect = Particle2dEffect {
+            lifetime: Rdist::new(10., 0.),
+            spawn_rate: 0.05,
+            linear_speed: None,
+            relative_positioning: Some(true),
+            collision: Some(CollisionResponse::Die),
+            ..Default::default()
+        };
+        let wall = [WorldCollider::new(
+            ColliderShape::Polyline(vec![Vec2::new(0., -20.), Vec2::new(0., 20.)]),
+            Vec2::new(50., 0.),
+            0.,
+        )];
+        let mut state = ParticleSpawnerState::default();
+        state.messages.enabled = ParticleMessages::ALL;
+        let mut store = ParticleStore::default();
+        for step in 0..10 {
+            tick_spawner(
+                &mut store,
+                &mut state,
+                &effect,
+                &Transform::from_xyz(step as f32 * 10., 0., 0.),
+                Duration::from_millis(50),
+                false,
+                Colliders {
+                    shapes: &wall,
+                    ..Default::default()
+                },
+            );
+        }
+
+        // the spawner moved across the wall, its particles did not
+        assert!(state.messages.collided.is_empty());
+        assert_eq!(store.len(), state.messages.spawned.len());
+        assert!(store.position_x.iter().all(|x| *x == 90.));
+    }
+
+    #[test]
+    fn new_particles_collide_in_their_first_frame() {
+        let effect = Particle2dEffect {
+            lifetime: Rdist::new(10., 0.),
+            spawn_per_second: Some(100.),
+            linear_speed: Some(Rdist::new(1000., 0.)),
+            direction: Some(Rval(Vec2::NEG_Y, 0.)),
+            collision: Some(CollisionResponse::Stick),
+            ..Default::default()
+        };
+        let ground = [WorldCollider::new(
+            ColliderShape::HalfPlane { normal: Vec2::Y },
+            Vec2::new(0., -20.),
+            0.,
+        )];
+        let mut state = ParticleSpawnerState::default();
+        let mut store = ParticleStore::default();
+        tick_spawner(
+            &mut store,
+            &mut state,
+            &effect,
+            &Transform::default(),
+            Duration::from_millis(100),
+            false,
+            Colliders {
+                shapes: &ground,
+                ..Default::default()
+            },
+        );
+
+        // pre-aged particles fell up to 100 units in the frame they spawned
+        assert_eq!(store.len(), 10);
+        assert!(store.position_y.iter().all(|y| *y >= -20.));
+        assert!(store.position_y.iter().filter(|y| **y == -20.).count() > 5);
+    }
+
+    #[test]
+    fn messages_are_collected_per_spawner() {
+        let effect = Particle2dEffect {
+            spawn_rate: 0.1,
+            spawn_amount: 2,
+            lifetime: Rdist::new(0.15, 0.),
+            linear_speed: Some(Rdist::new(100., 0.)),
+            direction: Some(Rval(Vec2::NEG_Y, 0.)),
+            collision: Some(CollisionResponse::Die),
+            ..Default::default()
+        };
+        let ground = [WorldCollider::new(
+            ColliderShape::HalfPlane { normal: Vec2::Y },
+            Vec2::new(0., -10.),
+            0.,
+        )];
+        let frame = |state: &mut ParticleSpawnerState| {
+            let mut store = ParticleStore::default();
+            for _ in 0..10 {
+                tick_spawner(
+                    &mut store,
+                    state,
+                    &effect,
+                    &Transform::default(),
+                    Duration::from_millis(50),
+                    false,
+                    Colliders {
+                        shapes: &ground,
+                        ..Default::default()
+                    },
+                );
+            }
+        };
+
+        let mut state = ParticleSpawnerState::default();
+        frame(&mut state);
+        assert!(state.messages.spawned.is_empty(), "not opted in");
+
+        let mut state = ParticleSpawnerState::default();
+        state.messages.enabled = ParticleMessages {
+            died: true,
+            collided: true,
+            ..Default::default()
+        };
+        frame(&mut state);
+        assert!(state.messages.spawned.is_empty());
+        // every particle dies on the ground after 0.1 seconds
+        assert!(!state.messages.died.is_empty());
+        assert_eq!(state.messages.collided.len(), state.messages.died.len());
+        assert!(state
+            .messages
+            .collided
+            .iter()
+            .all(|(particle, normal)| particle.position.y < -10. && *normal == Vec2::Y));
+
+        state.messages.enabled = ParticleMessages::ALL;
+        frame(&mut state);
+        assert!(!state.messages.spawned.is_empty());
+        assert_eq!(state.messages.spawned[0].velocity, Vec2::new(0., -100.));
+    }
+
+    #[test]
+    fn sub_emitters_trigger_at_parent_particles() {
+        let trigger = |trigger| SubEmitter {
+            inherit_velocity: Some(0.5),
+            inherit_color: Some(true),
+            ..SubEmitter::new(Default::default(), trigger, 3)
+        };
+        let effect = Particle2dEffect {
+            spawn_rate: 0.1,
+            lifetime: Rdist::new(0.25, 0.),
+            linear_speed: Some(Rdist::new(100., 0.)),
+            direction: Some(Rval(Vec2::X, 0.)),
+            color: Some(LinearRgba::RED),
+            sub_emitters: Some(vec![
+                trigger(SubEmitterTrigger::Birth),
+                trigger(SubEmitterTrigger::Death),
+                trigger(SubEmitterTrigger::Interval(0.1)),
+            ]),
+            ..Default::default()
+        };
+        let mut state = ParticleSpawnerState::default();
+        let mut store = ParticleStore::default();
+        let mut emits = Vec::new();
+        for _ in 0..10 {
+            tick_spawner(
+                &mut store,
+                &mut state,
+                &effect,
+                &Transform::default(),
+                Duration::from_millis(50),
+                false,
+                Colliders::default(),
+            );
+            emits.append(&mut state.sub_emits);
+        }
+
+        let count = |emitter| emits.iter().filter(|emit| emit.emitter == emitter).count();
+        // births every 0.1 seconds, of them two lived their 0.25 seconds
+        assert_eq!((count(0), count(1), count(2)), (5, 2, 7));
+
+        let death = emits.iter().find(|emit| emit.emitter == 1).unwrap();
+        assert!((death.position.x - 25.).abs() < 1e-3, "{death:?}");
+
+        let child = Particle2dEffect {
+            linear_speed: Some(Rdist::new(10., 0.)),
+            direction: Some(Rval(Vec2::Y, 0.)),
+            color: Some(LinearRgba::rgb(0.5, 0.5, 0.5)),
+            ..Default::default()
+        };
+        let mut store = ParticleStore::default();
+        let mut state = ParticleSpawnerState::default();
+        spawn_sub_particles(
+            &mut store,
+            &mut state,
+            &child,
+            death.position,
+            death.velocity * 0.5,
+            Some(death.color),
+            3,
+        );
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.position_x[0], death.position.x);
+        assert_eq!((store.velocity_x[0], store.velocity_y[0]), (50., 10.));
+        assert_eq!((store.color_r[0], store.color_g[0]), (0.5, 0.));
+    }
+
+    #[test]
+    fn sub_emitter_spawners_follow_their_parent() {
+        use crate::sub_emitter::{follow_parents, SubEmitterOf};
+        use bevy_ecs::{system::RunSystemOnce, world::World};
+
+        let mut world = World::new();
+        let parent_transform = Transform::from_xyz(10., 20., 1.);
+        let parent = world
+            .spawn((parent_transform, GlobalTransform::from(parent_transform)))
+            .id();
+        let child = world
+            .spawn((
+                Transform::default(),
+                GlobalTransform::default(),
+                SubEmitterOf { parent, index: 0 },
+            ))
+            .id();
+        let grandchild = world
+            .spawn((
+                Transform::default(),
+                GlobalTransform::default(),
+                SubEmitterOf {
+                    parent: child,
+                    index: 0,
+                },
+            ))
+            .id();
+
+        world.run_system_once(follow_parents).unwrap();
+        for entity in [child, grandchild] {
+            let global = world.get::<GlobalTransform>(entity).unwrap();
+            assert_eq!(global.translation(), Vec3::new(10., 20., 1.));
+            assert_eq!(*world.get::<Transform>(entity).unwrap(), parent_transform);
+        }
+    }
+
+    #[test]
+    fn sub_emitter_effects_fill_in_once_loaded() {
+        use crate::sub_emitter::{load_sub_emitter_effects, SubEmitterOf};
+        use bevy_asset::AssetEvent;
+        use bevy_ecs::{message::Messages, system::RunSystemOnce, world::World};
+
+        let mut world = World::new();
+        world.init_resource::<Assets<Particle2dEffect>>();
+        world.init_resource::<Messages<AssetEvent<Particle2dEffect>>>();
+        let handle = world
+            .resource_mut::<Assets<Particle2dEffect>>()
+            .reserve_handle();
+        let parent = world.spawn_empty().id();
+        let child = world
+            .spawn((
+                ParticleEffectInstance(None),
+                ParticleEffectHandle(handle.clone()),
+                SubEmitterOf { parent, index: 0 },
+            ))
+            .id();
+
+        world
+            .resource_mut::<Assets<Particle2dEffect>>()
+            .insert(
+                &handle,
+                Particle2dEffect {
+                    spawn_amount: 3,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        world.write_message(AssetEvent::LoadedWithDependencies { id: handle.id() });
+        world.run_system_once(load_sub_emitter_effects).unwrap();
+
+        let instance = world.get::<ParticleEffectInstance>(child).unwrap();
+        assert_eq!(
+            instance.0.as_ref().map(|effect| effect.spawn_amount),
+            Some(3)
+        );
+    }
+
     #[test]
     #[ignore = "manual performance benchmark"]
     fn bench_update_one_million_particles() {
@@ -684,6 +2605,7 @@ mod tests {
                 black_box(1.0 / 60.0),
                 Vec3::ZERO,
                 Vec3::ZERO,
+                0.,
             );
         }
 
@@ -696,6 +2618,7 @@ mod tests {
                 black_box(1.0 / 60.0),
                 Vec3::ZERO,
                 Vec3::ZERO,
+                0.,
             );
             samples.push(start.elapsed());
         }
@@ -1,4 +1,7 @@
-use bevy_math::Vec2;
+use crate::curve::MultiCurve;
+use bevy_color::{Hsva, LinearRgba};
+use bevy_math::{Vec2, Vec4};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Clone, Debug, Serialize, Default)]
@@ -11,18 +14,24 @@ impl<V> Rval<V> {
 }
 
 pub trait Random<V> {
-    fn rand(&self) -> V;
+    /// random value from the thread local generator
+    fn rand(&self) -> V {
+        self.rand_with(&mut rand::rng())
+    }
+
+    /// random value drawn from `rng`, used for reproducible effects
+    fn rand_with<R: Rng + ?Sized>(&self, rng: &mut R) -> V;
 }
 
 impl Random<Vec2> for Rval<Vec2> {
-    fn rand(&self) -> Vec2 {
+    fn rand_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec2 {
         // Skip, if not needed.
         if self.1 <= 0.0001 {
             return self.0;
         }
 
         let max_angle = 2. * std::f32::consts::PI * self.1;
-        let random_angle = (rand::random::<f32>() - 0.5) * max_angle;
+        let random_angle = (rng.random::<f32>() - 0.5) * max_angle;
 
         let (sin, cos) = random_angle.sin_cos();
         Vec2::new(
@@ -33,8 +42,218 @@ impl Random<Vec2> for Rval<Vec2> {
 }
 
 impl Random<f32> for Rval<f32> {
-    fn rand(&self) -> f32 {
-        let r = (rand::random::<f32>() - 0.5) * 2. * self.1;
+    fn rand_with<R: Rng + ?Sized>(&self, rng: &mut R) -> f32 {
+        let r = (rng.random::<f32>() - 0.5) * 2. * self.1;
         self.0 + self.0 * r
     }
 }
+
+/// A random scalar. The plain tuple `(value, randomness)` is the
+/// percent mode of `Rval<f32>`, all other modes are written with
+/// their name, e.g. `Range(-5., 5.)`.
+#[derive(Deserialize, Clone, Debug, Serialize)]
+pub enum Rdist {
+    /// uniform between min and max
+    Range(f32, f32),
+    /// normal distribution, optionally clamped
+    Normal {
+        mean: f32,
+        std_dev: f32,
+        min: Option<f32>,
+        max: Option<f32>,
+    },
+    /// one of the values, picked by weight `(value, weight)`
+    Weighted(Vec<(f32, f32)>),
+    /// value of the curve at a random position
+    Curve(MultiCurve<f32>),
+    /// value ± value * randomness
+    #[serde(untagged)]
+    Percent(f32, f32),
+}
+
+impl Default for Rdist {
+    fn default() -> Self {
+        Self::Percent(0., 0.)
+    }
+}
+
+impl Rdist {
+    /// percent mode, value ± value * randomness
+    pub fn new(value: f32, randomness: f32) -> Self {
+        Self::Percent(value, randomness)
+    }
+
+    /// sorts the curve of the curve mode
+    pub fn sort(&mut self) {
+        if let Rdist::Curve(curve) = self {
+            curve.sort();
+        }
+    }
+}
+
+impl From<Rval<f32>> for Rdist {
+    fn from(value: Rval<f32>) -> Self {
+        Self::Percent(value.0, value.1)
+    }
+}
+
+impl Random<f32> for Rdist {
+    fn rand_with<R: Rng + ?Sized>(&self, rng: &mut R) -> f32 {
+        match self {
+            Rdist::Percent(value, randomness) => Rval(*value, *randomness).rand_with(rng),
+            Rdist::Range(min, max) => min + (max - min) * rng.random::<f32>(),
+            Rdist::Normal {
+                mean,
+                std_dev,
+                min,
+                max,
+            } => {
+                // Box-Muller transform
+                let u = 1. - rng.random::<f32>();
+                let v = rng.random::<f32>();
+                let z = (-2. * u.ln()).sqrt() * (std::f32::consts::TAU * v).cos();
+                (mean + z * std_dev)
+                    .max(min.unwrap_or(f32::MIN))
+                    .min(max.unwrap_or(f32::MAX))
+            }
+            Rdist::Weighted(choices) => {
+                let total: f32 = choices.iter().map(|(_, weight)| weight.max(0.)).sum();
+                let mut target = rng.random::<f32>() * total;
+                for (value, weight) in choices {
+                    target -= weight.max(0.);
+                    if target < 0. {
+                        return *value;
+                    }
+                }
+                choices.last().map_or(0., |(value, _)| *value)
+            }
+            Rdist::Curve(curve) => curve.lerp(rng.random::<f32>()),
+        }
+    }
+}
+
+/// A random birth color.
+#[derive(Deserialize, Clone, Debug, Serialize)]
+pub enum ColorDist {
+    /// color of the gradient at a random position
+    Gradient(MultiCurve<LinearRgba>),
+    /// one of the colors, equally likely
+    Palette(Vec<LinearRgba>),
+    /// every channel ± its amount, `amount` is rgba
+    Jitter { color: LinearRgba, amount: Vec4 },
+    /// hue in degrees, saturation and value ± their amount
+    Hsv {
+        color: LinearRgba,
+        hue: f32,
+        saturation: f32,
+        value: f32,
+    },
+}
+
+impl ColorDist {
+    /// sorts the gradient of the gradient mode
+    pub fn sort(&mut self) {
+        if let ColorDist::Gradient(curve) = self {
+            curve.sort();
+        }
+    }
+}
+
+impl Random<LinearRgba> for ColorDist {
+    fn rand_with<R: Rng + ?Sized>(&self, rng: &mut R) -> LinearRgba {
+        let mut offset = |amount: f32| (rng.random::<f32>() - 0.5) * 2. * amount;
+        match self {
+            ColorDist::Gradient(curve) => curve.lerp(rng.random::<f32>()),
+            ColorDist::Palette(colors) => {
+                if colors.is_empty() {
+                    return LinearRgba::WHITE;
+                }
+                colors[rng.random_range(0..colors.len())]
+            }
+            ColorDist::Jitter { color, amount } => {
+                let channels = Vec4::new(
+                    color.red + offset(amount.x),
+                    color.green + offset(amount.y),
+                    color.blue + offset(amount.z),
+                    color.alpha + offset(amount.w),
+                )
+                .max(Vec4::ZERO);
+                LinearRgba::new(channels.x, channels.y, channels.z, channels.w.min(1.))
+            }
+            ColorDist::Hsv {
+                color,
+                hue,
+                saturation,
+                value,
+            } => {
+                let mut hsva = Hsva::from(*color);
+                hsva.hue = (hsva.hue + offset(*hue)).rem_euclid(360.);
+                hsva.saturation = (hsva.saturation + offset(*saturation)).clamp(0., 1.);
+                hsva.value = (hsva.value + offset(*value)).max(0.);
+                hsva.into()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::ParticleRng;
+
+    #[test]
+    fn rdist_reads_the_legacy_tuple() {
+        let values: Vec<Rdist> = ron::de::from_str(
+            "[(100., 0.1), Range(-5, 5), Normal(mean: 1, std_dev: 0.5, min: Some(0)), Weighted([(1, 1), (2, 3)])]",
+        )
+        .unwrap();
+        assert!(matches!(values[0], Rdist::Percent(100., 0.1)));
+        let migrated: Rdist = Rval(100., 0.1).into();
+        assert!(matches!(migrated, Rdist::Percent(100., 0.1)));
+        assert!(matches!(values[1], Rdist::Range(-5., 5.)));
+        assert!(matches!(
+            values[2],
+            Rdist::Normal {
+                min: Some(0.),
+                max: None,
+                ..
+            }
+        ));
+        assert_eq!(
+            ron::ser::to_string(&values[0]).unwrap(),
+            "(100.0,0.1)",
+            "percent mode is written as tuple"
+        );
+
+        let mut rng = ParticleRng::new(0);
+        for _ in 0..1000 {
+            let value = values[1].rand_with(&mut rng);
+            assert!((-5. ..=5.).contains(&value));
+            assert!(values[2].rand_with(&mut rng) >= 0.);
+            assert!([1., 2.].contains(&values[3].rand_with(&mut rng)));
+        }
+    }
+
+    #[test]
+    fn colors_stay_in_their_distribution() {
+        let colors: Vec<ColorDist> = ron::de::from_str(
+            "[Palette([LinearRgba(red: 1, green: 0, blue: 0, alpha: 1), LinearRgba(red: 0, green: 0, blue: 1, alpha: 1)]), \
+             Jitter(color: LinearRgba(red: 0.5, green: 0.5, blue: 0.5, alpha: 1), amount: (0.1, 0, 0, 0)), \
+             Hsv(color: LinearRgba(red: 1, green: 0, blue: 0, alpha: 1), hue: 30, saturation: 0, value: 0)]",
+        )
+        .unwrap();
+
+        let mut rng = ParticleRng::new(0);
+        for _ in 0..1000 {
+            let color = colors[0].rand_with(&mut rng);
+            assert!(color == LinearRgba::RED || color == LinearRgba::BLUE);
+
+            let color = colors[1].rand_with(&mut rng);
+            assert!((0.4..=0.6).contains(&color.red));
+            assert_eq!((color.green, color.alpha), (0.5, 1.));
+
+            let hue = Hsva::from(colors[2].rand_with(&mut rng)).hue;
+            assert!(hue <= 30.01 || hue >= 329.99, "{hue}");
+        }
+    }
+}
@@ -90,6 +90,12 @@ pub(crate) fn scene_gui(ui: &mut Ui, settings: &mut SceneSettings) {
 enum Shape {
     Point,
     Circle,
+    Rectangle,
+    Annulus,
+    Arc,
+    Segment,
+    Polygon,
+    Image,
 }
 
 impl From<Shape> for &'static str {
@@ -97,6 +103,12 @@ impl From<Shape> for &'static str {
         match val {
             Shape::Point => "Point",
             Shape::Circle => "Circle",
+            Shape::Rectangle => "Rectangle",
+            Shape::Annulus => "Annulus",
+            Shape::Arc => "Arc",
+            Shape::Segment => "Segment",
+            Shape::Polygon => "Polygon",
+            Shape::Image => "Image",
         }
     }
 }
@@ -113,6 +125,12 @@ impl From<&EmissionShape> for Shape {
         match value {
             EmissionShape::Point => Self::Point,
             EmissionShape::Circle(_) => Self::Circle,
+            EmissionShape::Rectangle { .. } => Self::Rectangle,
+            EmissionShape::Annulus { .. } => Self::Annulus,
+            EmissionShape::Arc { .. } => Self::Arc,
+            EmissionShape::Segment { .. } => Self::Segment,
+            EmissionShape::Polygon(_) => Self::Polygon,
+            EmissionShape::Image(_) => Self::Image,
         }
     }
 }
@@ -164,27 +182,88 @@ pub(crate) fn config_gui(
             egui::ComboBox::from_label("")
                 .selected_text(shape)
                 .show_ui(ui, |ui| {
-                    ui.selectable_value(&mut shape, Shape::Point, Shape::Point);
-                    ui.selectable_value(&mut shape, Shape::Circle, Shape::Circle);
+                    for option in [
+                        Shape::Point,
+                        Shape::Circle,
+                        Shape::Rectangle,
+                        Shape::Annulus,
+                        Shape::Arc,
+                        Shape::Segment,
+                        Shape::Polygon,
+                    ] {
+                        ui.selectable_value(&mut shape, option, option);
+                    }
                 });
             if before != shape {
                 effect.emission_shape = match shape {
                     Shape::Point => EmissionShape::Point,
                     Shape::Circle => EmissionShape::Circle(15.0),
+                    Shape::Rectangle => EmissionShape::Rectangle {
+                        half_size: Vec2::splat(15.0),
+                    },
+                    Shape::Annulus => EmissionShape::Annulus {
+                        inner_radius: 10.0,
+                        outer_radius: 15.0,
+                    },
+                    Shape::Arc => EmissionShape::Arc {
+                        radius: 15.0,
+                        half_angle: std::f32::consts::FRAC_PI_4,
+                    },
+                    Shape::Segment => EmissionShape::Segment {
+                        start: Vec2::new(-15.0, 0.0),
+                        end: Vec2::new(15.0, 0.0),
+                    },
+                    Shape::Polygon => EmissionShape::Polygon(EmissionPolygon::new([
+                        Vec2::new(-15.0, -15.0),
+                        Vec2::new(15.0, -15.0),
+                        Vec2::new(0.0, 15.0),
+                    ])),
+                    // needs an image, can only be set in the effect file
+                    Shape::Image => effect.emission_shape.clone(),
                 };
-            } else {
-                let mut val = match effect.emission_shape {
-                    EmissionShape::Point => {
-                        return;
-                    }
-                    EmissionShape::Circle(val) => val,
-                };
-                if ui.add(egui::DragValue::new(&mut val)).changed() {
-                    effect.emission_shape = EmissionShape::Circle(val);
+                return;
+            }
+
+            match &mut effect.emission_shape {
+                EmissionShape::Point => {}
+                EmissionShape::Circle(radius) => {
+                    ui.add(egui::DragValue::new(radius));
+                }
+                EmissionShape::Rectangle { half_size } => {
+                    ui.add(egui::DragValue::new(&mut half_size.x).prefix("Half W: "));
+                    ui.add(egui::DragValue::new(&mut half_size.y).prefix("Half H: "));
+                }
+                EmissionShape::Annulus {
+                    inner_radius,
+                    outer_radius,
+                } => {
+                    ui.add(egui::DragValue::new(inner_radius).prefix("Inner: "));
+                    ui.add(egui::DragValue::new(outer_radius).prefix("Outer: "));
+                }
+                EmissionShape::Arc { radius, half_angle } => {
+                    ui.add(egui::DragValue::new(radius).prefix("R: "));
+                    ui.add(egui::Slider::new(half_angle, 0.0..=std::f32::consts::PI));
+                }
+                EmissionShape::Segment { start, end } => {
+                    ui.add(egui::DragValue::new(&mut start.x).prefix("X1: "));
+                    ui.add(egui::DragValue::new(&mut start.y).prefix("Y1: "));
+                    ui.add(egui::DragValue::new(&mut end.x).prefix("X2: "));
+                    ui.add(egui::DragValue::new(&mut end.y).prefix("Y2: "));
+                }
+                EmissionShape::Polygon(_) => {
+                    ui.label("edit vertices in the effect file");
+                }
+                EmissionShape::Image(mask) => {
+                    ui.label(&mask.path);
                 }
             }
         });
-        rval_f32_field(ui, "Lifetime", &mut effect.lifetime);
+
+        let mut emit_from_edge = effect.emit_from_edge.unwrap_or(false);
+        ui.checkbox(&mut emit_from_edge, "Emit from edge");
+        effect.emit_from_edge = Some(emit_from_edge);
+
+        rdist_field(ui, "Lifetime", &mut effect.lifetime);
     });
     ui.separator();
     collapsing_header("Linear velocity").show(ui, |ui| {
@@ -195,41 +274,73 @@ pub(crate) fn config_gui(
         }
 
         if let Some(mut speed) = effect.linear_speed.as_mut() {
-            rval_f32_field(ui, "Speed", speed);
+            rdist_field(ui, "Speed", speed);
         } else {
-            effect.linear_speed = Some(Rval::default());
+            effect.linear_speed = Some(Rdist::default());
         }
 
         if let Some(mut damp) = effect.linear_damp.as_mut() {
-            rval_f32_field(ui, "Damp", damp);
+            rdist_field(ui, "Damp", damp);
         } else {
-            effect.linear_damp = Some(Rval::default());
+            effect.linear_damp = Some(Rdist::default());
         }
 
         if let Some(mut accel) = effect.linear_acceleration.as_mut() {
-            rval_f32_field(ui, "Accel", accel);
+            rdist_field(ui, "Accel", accel);
+        } else {
+            effect.linear_acceleration = Some(Rdist::default());
+        }
+
+        if let Some(mut radial) = effect.radial_acceleration.as_mut() {
+            rdist_field(ui, "Radial Accel", radial);
+        } else {
+            effect.radial_acceleration = Some(Rdist::default());
+        }
+
+        if let Some(mut tangential) = effect.tangential_acceleration.as_mut() {
+            rdist_field(ui, "Tangent Accel", tangential);
         } else {
-            effect.linear_acceleration = Some(Rval::default());
+            effect.tangential_acceleration = Some(Rdist::default());
         }
     });
     ui.separator();
     collapsing_header("Angular velocity").show(ui, |ui| {
         if let Some(mut speed) = effect.angular_speed.as_mut() {
-            rval_f32_field(ui, "Speed", speed);
+            rdist_field(ui, "Speed", speed);
         } else {
-            effect.angular_speed = Some(Rval::default());
+            effect.angular_speed = Some(Rdist::default());
         }
 
         if let Some(mut damp) = effect.angular_damp.as_mut() {
-            rval_f32_field(ui, "Damp", damp);
+            rdist_field(ui, "Damp", damp);
         } else {
-            effect.angular_damp = Some(Rval::default());
+            effect.angular_damp = Some(Rdist::default());
         }
 
         if let Some(mut accel) = effect.angular_acceleration.as_mut() {
-            rval_f32_field(ui, "Accel", accel);
+            rdist_field(ui, "Accel", accel);
+        } else {
+            effect.angular_acceleration = Some(Rdist::default());
+        }
+
+        if let Some(mut rotation) = effect.rotation.as_mut() {
+            rdist_field(ui, "Init Rotation", rotation);
         } else {
-            effect.angular_acceleration = Some(Rval::default());
+            effect.rotation = Some(Rdist::default());
+        }
+
+        let mut align = matches!(
+            effect.rotation_mode,
+            Some(RotationMode::AlignToVelocity { .. })
+        );
+        ui.checkbox(&mut align, "Align to velocity");
+        if !align {
+            effect.rotation_mode = None;
+        } else if let Some(RotationMode::AlignToVelocity { offset }) = effect.rotation_mode.as_mut()
+        {
+            ui.add(slider(offset, -std::f32::consts::PI..=std::f32::consts::PI));
+        } else {
+            effect.rotation_mode = Some(RotationMode::AlignToVelocity { offset: 0.0 });
         }
     });
 
@@ -242,9 +353,106 @@ pub(crate) fn config_gui(
         }
 
         if let Some(mut grav) = effect.gravity_speed.as_mut() {
-            rval_f32_field(ui, "Speed", grav);
+            rdist_field(ui, "Speed", grav);
+        } else {
+            effect.gravity_speed = Some(Rdist::default());
+        }
+    });
+
+    ui.separator();
+    collapsing_header("Turbulence").show(ui, |ui| {
+        let mut enabled = effect.turbulence.is_some();
+        ui.checkbox(&mut enabled, "Enabled");
+        if !enabled {
+            effect.turbulence = None;
+        } else if let Some(turbulence) = effect.turbulence.as_mut() {
+            egui::Grid::new("turbulence")
+                .spacing([4., 4.])
+                .min_col_width(80.)
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Strength");
+                    ui.add(slider(&mut turbulence.strength, 0.0..=5000.0).logarithmic(true));
+                    ui.end_row();
+
+                    ui.label("Frequency");
+                    ui.add(slider(&mut turbulence.frequency, 0.001..=1.0).logarithmic(true));
+                    ui.end_row();
+
+                    let mut octaves = turbulence.octaves.unwrap_or(1);
+                    ui.label("Octaves");
+                    ui.add(slider(&mut octaves, 1..=6));
+                    turbulence.octaves = Some(octaves);
+                    ui.end_row();
+
+                    let mut scroll = turbulence.scroll.unwrap_or_default();
+                    ui.label("Scroll");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut scroll.x).prefix("X: "));
+                        ui.add(egui::DragValue::new(&mut scroll.y).prefix("Y: "));
+                    });
+                    turbulence.scroll = Some(scroll);
+                    ui.end_row();
+
+                    let mut curl = !matches!(turbulence.mode, Some(NoiseMode::Value));
+                    ui.checkbox(&mut curl, "Curl");
+                    turbulence.mode = Some(if curl {
+                        NoiseMode::Curl
+                    } else {
+                        NoiseMode::Value
+                    });
+                });
         } else {
-            effect.gravity_speed = Some(Rval::default());
+            effect.turbulence = Some(Turbulence::new(100.0, 0.02));
+        }
+    });
+
+    ui.separator();
+    collapsing_header("Collision").show(ui, |ui| {
+        let bounce = CollisionResponse::Bounce {
+            restitution: 0.5,
+            friction: 0.1,
+        };
+        let name = |response: &Option<CollisionResponse>| match response {
+            None => "None",
+            Some(CollisionResponse::Bounce { .. }) => "Bounce",
+            Some(CollisionResponse::Stick) => "Stick",
+            Some(CollisionResponse::Die) => "Die",
+        };
+        egui::ComboBox::new("collision_response", "")
+            .selected_text(name(&effect.collision))
+            .show_ui(ui, |ui| {
+                for option in [
+                    None,
+                    Some(bounce),
+                    Some(CollisionResponse::Stick),
+                    Some(CollisionResponse::Die),
+                ] {
+                    let selected = name(&effect.collision) == name(&option);
+                    if ui.selectable_label(selected, name(&option)).clicked() && !selected {
+                        effect.collision = option;
+                    }
+                }
+            });
+
+        if let Some(CollisionResponse::Bounce {
+            restitution,
+            friction,
+        }) = effect.collision.as_mut()
+        {
+            egui::Grid::new("collision")
+                .spacing([4., 4.])
+                .min_col_width(80.)
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Restitution");
+                    ui.add(slider(restitution, 0.0..=1.0));
+                    ui.end_row();
+
+                    ui.label("Friction");
+                    ui.add(slider(friction, 0.0..=1.0));
+                    ui.end_row();
+                });
         }
     });
 
@@ -283,15 +491,54 @@ pub(crate) fn config_gui(
                         });
                         ui.end_row();
 
+                        let mut strength = attractor.strength.abs();
+                        let mut repel = attractor.strength < 0.0;
                         ui.label("Strength");
-                        ui.add(
-                            slider(&mut attractor.strength, 0.0..=100000000.0).logarithmic(true),
-                        );
+                        ui.add(slider(&mut strength, 0.0..=100000000.0).logarithmic(true));
+                        ui.checkbox(&mut repel, "Repel");
+                        attractor.strength = if repel { -strength } else { strength };
                         ui.end_row();
 
                         ui.label("Min Distance");
                         ui.add(slider(&mut attractor.min_distance, 0.1..=100.0));
                         ui.end_row();
+
+                        let mut falloff = attractor.falloff.unwrap_or_default();
+                        ui.label("Falloff");
+                        egui::ComboBox::new(format!("attractor_falloff_{i}"), "")
+                            .selected_text(format!("{falloff:?}"))
+                            .show_ui(ui, |ui| {
+                                for option in [
+                                    Falloff::Constant,
+                                    Falloff::Linear,
+                                    Falloff::Inverse,
+                                    Falloff::InverseSquare,
+                                    Falloff::Smoothstep,
+                                ] {
+                                    ui.selectable_value(
+                                        &mut falloff,
+                                        option,
+                                        format!("{option:?}"),
+                                    );
+                                }
+                            });
+                        attractor.falloff = Some(falloff);
+                        ui.end_row();
+
+                        for (name, value) in [
+                            ("Radius", &mut attractor.radius),
+                            ("Kill Radius", &mut attractor.kill_radius),
+                        ] {
+                            let mut enabled = value.is_some();
+                            ui.checkbox(&mut enabled, name);
+                            if enabled {
+                                let value = value.get_or_insert(50.0);
+                                ui.add(slider(value, 0.0..=1000.0));
+                            } else {
+                                *value = None;
+                            }
+                            ui.end_row();
+                        }
                     });
                 ui.separator();
             }
@@ -305,20 +552,12 @@ pub(crate) fn config_gui(
             }
 
             if ui.button("Add Attractor").clicked() {
-                attractors.push(Attractor {
-                    position: Vec2::new(0.0, 0.0),
-                    strength: 10000.0,
-                    min_distance: 5.0,
-                });
+                attractors.push(Attractor::new(Vec2::new(0.0, 0.0), 10000.0, 5.0));
             }
         } else {
             ui.label("No attractors defined");
             if ui.button("Add First Attractor").clicked() {
-                effect.attractors = Some(vec![Attractor {
-                    position: Vec2::new(0.0, 0.0),
-                    strength: 10000.0,
-                    min_distance: 5.0,
-                }]);
+                effect.attractors = Some(vec![Attractor::new(Vec2::new(0.0, 0.0), 10000.0, 5.0)]);
             }
         }
 
@@ -336,9 +575,19 @@ pub(crate) fn config_gui(
             }
         } else {
             if let Some(mut scale) = effect.scale.as_mut() {
-                rval_f32_field(ui, "Init Scale", scale);
+                rdist_field(ui, "Init Scale", scale);
+            } else {
+                effect.scale = Some(Rdist::default());
+            }
+
+            let mut separate_y = effect.scale_y.is_some();
+            ui.checkbox(&mut separate_y, "Separate Y Scale");
+            if !separate_y {
+                effect.scale_y = None;
+            } else if let Some(scale_y) = effect.scale_y.as_mut() {
+                rdist_field(ui, "Init Scale Y", scale_y);
             } else {
-                effect.scale = Some(Rval::default());
+                effect.scale_y = effect.scale.clone();
             }
 
             if ui.button("Add Scale Curve").clicked() {
@@ -348,6 +597,24 @@ pub(crate) fn config_gui(
                 effect.scale_curve = Some(curve);
             }
         }
+
+        let mut stretch = effect.stretch.is_some();
+        ui.checkbox(&mut stretch, "Stretch by speed");
+        if !stretch {
+            effect.stretch = None;
+        } else if let Some(stretch) = effect.stretch.as_mut() {
+            ui.label("Factor");
+            ui.add(slider(&mut stretch.factor, 0.0..=0.1));
+            let mut max = stretch.max.unwrap_or(10.0);
+            ui.label("Max");
+            ui.add(slider(&mut max, 1.0..=50.0));
+            stretch.max = Some(max);
+        } else {
+            effect.stretch = Some(VelocityStretch {
+                factor: 0.01,
+                max: Some(10.0),
+            });
+        }
     });
 
     ui.separator();
@@ -357,6 +624,10 @@ pub(crate) fn config_gui(
             if color_curve.points.is_empty() {
                 effect.color_curve = None;
             }
+
+            let mut multiply = effect.color_curve_mode == Some(ColorCurveMode::Multiply);
+            ui.checkbox(&mut multiply, "Multiply birth color");
+            effect.color_curve_mode = multiply.then_some(ColorCurveMode::Multiply);
         } else {
             if let Some(mut color) = effect.color.as_mut() {
                 ui.label("Color");
@@ -375,25 +646,91 @@ pub(crate) fn config_gui(
     });
 }
 
-fn rval_f32_field(ui: &mut Ui, label: &str, field: &mut Rval<f32>) {
+fn rdist_field(ui: &mut Ui, label: &str, field: &mut Rdist) {
     ui.add_space(5.0);
     egui::Grid::new(label)
         .spacing([4., 4.])
         .min_col_width(80.)
         .num_columns(2)
         .show(ui, |ui| {
-            ui.label(label);
-            ui.add(slider(&mut field.0, 0.0..=9999.9).logarithmic(true));
-
+            ui.label(format!("{label} Mode"));
+            let mode = match field {
+                Rdist::Percent(..) => "Percent",
+                Rdist::Range(..) => "Range",
+                Rdist::Normal { .. } => "Normal",
+                Rdist::Weighted(_) => "Weighted",
+                Rdist::Curve(_) => "Curve",
+            };
+            egui::ComboBox::new(format!("{label}_mode"), "")
+                .selected_text(mode)
+                .show_ui(ui, |ui| {
+                    if ui.selectable_label(mode == "Percent", "Percent").clicked() {
+                        *field = Rdist::new(1.0, 0.0);
+                    }
+                    if ui.selectable_label(mode == "Range", "Range").clicked() {
+                        *field = Rdist::Range(0.0, 1.0);
+                    }
+                    if ui.selectable_label(mode == "Normal", "Normal").clicked() {
+                        *field = Rdist::Normal {
+                            mean: 1.0,
+                            std_dev: 0.1,
+                            min: None,
+                            max: None,
+                        };
+                    }
+                });
             ui.end_row();
 
-            if field.1 <= 0.001 {
-                if ui.button(format!("{label} Rand")).clicked() {
-                    field.1 = 0.1;
+            match field {
+                Rdist::Percent(value, randomness) => {
+                    ui.label(label);
+                    ui.add(slider(value, 0.0..=9999.9).logarithmic(true));
+
+                    ui.end_row();
+
+                    if *randomness <= 0.001 {
+                        if ui.button(format!("{label} Rand")).clicked() {
+                            *randomness = 0.1;
+                        }
+                    } else {
+                        ui.label(format!("{label} Rand"));
+                        ui.add(slider(randomness, 0.0..=1.0));
+                    }
+                }
+                Rdist::Range(min, max) => {
+                    ui.label("Min");
+                    ui.add(egui::DragValue::new(min).speed(0.1));
+                    ui.end_row();
+                    ui.label("Max");
+                    ui.add(egui::DragValue::new(max).speed(0.1));
+                }
+                Rdist::Normal {
+                    mean,
+                    std_dev,
+                    min,
+                    max,
+                } => {
+                    ui.label("Mean");
+                    ui.add(egui::DragValue::new(mean).speed(0.1));
+                    ui.end_row();
+                    ui.label("Std Dev");
+                    ui.add(egui::DragValue::new(std_dev).speed(0.01));
+                    ui.end_row();
+                    for (name, bound) in [("Min", min), ("Max", max)] {
+                        let mut enabled = bound.is_some();
+                        ui.checkbox(&mut enabled, name);
+                        if enabled {
+                            let value = bound.get_or_insert(*mean);
+                            ui.add(egui::DragValue::new(value).speed(0.1));
+                        } else {
+                            *bound = None;
+                        }
+                        ui.end_row();
+                    }
+                }
+                Rdist::Weighted(_) | Rdist::Curve(_) => {
+                    ui.label("edit in the effect file");
                 }
-            } else {
-                ui.label(format!("{label} Rand"));
-                ui.add(slider(&mut field.1, 0.0..=1.0));
             }
         });
     ui.add_space(5.0);
@@ -231,19 +231,42 @@ fn gizmo(
     };
 
     for transform in query.iter() {
-        match effect.emission_shape {
+        let position = transform.translation.xy();
+        let color = Color::LinearRgba(LinearRgba::RED);
+        match &effect.emission_shape {
             EmissionShape::Point => {
-                gizmos.circle_2d(
-                    transform.translation.xy(),
-                    2.0,
-                    Color::LinearRgba(LinearRgba::RED),
-                );
+                gizmos.circle_2d(position, 2.0, color);
             }
             EmissionShape::Circle(radius) => {
-                gizmos.circle_2d(
-                    transform.translation.xy(),
-                    radius,
-                    Color::LinearRgba(LinearRgba::RED),
+                gizmos.circle_2d(position, *radius, color);
+            }
+            EmissionShape::Rectangle { half_size } => {
+                gizmos.rect_2d(position, *half_size * 2.0, color);
+            }
+            EmissionShape::Annulus {
+                inner_radius,
+                outer_radius,
+            } => {
+                gizmos.circle_2d(position, *inner_radius, color);
+                gizmos.circle_2d(position, *outer_radius, color);
+            }
+            EmissionShape::Arc { radius, half_angle } => {
+                gizmos.arc_2d(position, *half_angle * 2.0, *radius, color);
+            }
+            EmissionShape::Segment { start, end } => {
+                gizmos.line_2d(position + *start, position + *end, color);
+            }
+            EmissionShape::Image(_) => {
+                gizmos.circle_2d(position, 2.0, color);
+            }
+            EmissionShape::Polygon(polygon) => {
+                let vertices = polygon.vertices();
+                gizmos.linestrip_2d(
+                    vertices
+                        .iter()
+                        .chain(vertices.first())
+                        .map(|vertex| position + *vertex),
+                    color,
                 );
             }
         };
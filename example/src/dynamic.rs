@@ -59,7 +59,7 @@ fn change_dynamic(
     };
 
     if let Some(effect) = maybe_effect.0.as_mut() {
-        effect.linear_speed = Some(Rval::new(1000. * elapsed.sin().abs(), 0.1));
+        effect.linear_speed = Some(Rdist::new(1000. * elapsed.sin().abs(), 0.1));
         effect.spawn_amount = 100;
     }
 }
@@ -0,0 +1,22 @@
+use bevy::prelude::*;
+use bevy_enoki::prelude::*;
+mod utils;
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(EnokiPlugin)
+        .add_systems(Startup, setup)
+        .add_plugins(utils::camera_and_ui_plugin)
+        .run();
+}
+
+fn setup(mut cmds: Commands, server: Res<AssetServer>) {
+    // every shell of the firework bursts into sparks, defined by the
+    // sub emitters of the effect file
+    cmds.spawn((
+        ParticleSpawner::default(),
+        ParticleEffectHandle(server.load("firework_sparks.particle.ron")),
+        Transform::from_xyz(0., -200., 0.),
+    ));
+}